@@ -0,0 +1,129 @@
+use merkle_tree::json::{root_from_json, root_to_json};
+use merkle_tree::proof::MerkleProof;
+use merkle_tree::Hash;
+use scrach_json_parser::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Hex,
+    Json,
+    Binary,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Format, String> {
+        match name {
+            "hex" => Ok(Format::Hex),
+            "json" => Ok(Format::Json),
+            "binary" => Ok(Format::Binary),
+            _ => Err(format!("unknown format: {}", name)),
+        }
+    }
+}
+
+/// One line of compact JSON.
+fn json_line(value: &Value) -> Vec<u8> {
+    let mut bytes = scrach_json_parser::to_string(value)
+        .expect("hashes and sizes are finite")
+        .into_bytes();
+    bytes.push(b'\n');
+    bytes
+}
+
+pub fn encode_root(root: &Hash, format: Format) -> Vec<u8> {
+    match format {
+        Format::Hex => format!("{}\n", root.value()).into_bytes(),
+        Format::Json => json_line(&root_to_json(root)),
+        Format::Binary => root.to_bytes().to_vec(),
+    }
+}
+
+pub fn decode_root(bytes: &[u8], format: Format) -> Result<Hash, String> {
+    let invalid = || "invalid root".to_string();
+    match format {
+        Format::Hex => {
+            let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+            Hash::from_hex(text.trim()).ok_or_else(invalid)
+        }
+        Format::Json => {
+            let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+            let value = scrach_json_parser::from_str(text).map_err(|e| e.to_string())?;
+            root_from_json(&value).map_err(|e| e.to_string())
+        }
+        Format::Binary => {
+            let bytes: &[u8; 32] = bytes.try_into().map_err(|_| invalid())?;
+            Ok(Hash::from_bytes(bytes))
+        }
+    }
+}
+
+pub fn encode_proof(proof: &MerkleProof, format: Format) -> Vec<u8> {
+    match format {
+        Format::Hex => format!("{}\n", hex::encode(proof.encode())).into_bytes(),
+        Format::Json => json_line(&proof.to_json()),
+        Format::Binary => proof.encode(),
+    }
+}
+
+pub fn decode_proof(bytes: &[u8], format: Format) -> Result<MerkleProof, String> {
+    match format {
+        Format::Hex => {
+            let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+            let bytes = hex::decode(text.trim()).map_err(|e| e.to_string())?;
+            MerkleProof::decode(&bytes).map_err(|e| e.to_string())
+        }
+        Format::Json => {
+            let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+            let value = scrach_json_parser::from_str(text).map_err(|e| e.to_string())?;
+            MerkleProof::from_json(&value).map_err(|e| e.to_string())
+        }
+        Format::Binary => MerkleProof::decode(bytes).map_err(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merkle_tree::Tree;
+
+    #[test]
+    fn proof_round_trip() {
+        let mut tree = Tree::new(vec!["a", "b", "c", "d", "e"]);
+        tree.build_tree();
+        let proof = tree.get_proof(3).unwrap();
+        for format in [Format::Hex, Format::Json, Format::Binary] {
+            let bytes = encode_proof(&proof, format);
+            assert_eq!(decode_proof(&bytes, format), Ok(proof.clone()));
+        }
+    }
+
+    #[test]
+    fn root_round_trip() {
+        let mut tree = Tree::new(vec!["a", "b", "c"]);
+        tree.build_tree();
+        for format in [Format::Hex, Format::Json, Format::Binary] {
+            let bytes = encode_root(&tree.root, format);
+            assert_eq!(decode_root(&bytes, format), Ok(tree.root.clone()));
+        }
+        let json = String::from_utf8(encode_root(&tree.root, Format::Json)).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                "{{\"algorithm\":\"sha256\",\"root\":\"{}\"}}\n",
+                tree.root.value()
+            )
+        );
+        assert!(decode_root(b"{\"root\": 1}", Format::Json).is_err());
+        assert!(decode_root(&[0; 31], Format::Binary).is_err());
+    }
+
+    #[test]
+    fn invalid_json_proof() {
+        let json =
+            br#"{"algorithm": "sha256", "tree_size": 2, "index": 0, "path": [{"hash": "00", "position": "right"}]}"#;
+        assert_eq!(
+            decode_proof(json, Format::Json),
+            Err("invalid hash: 00".to_string())
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use merkle_tree::{hash_bytes, Hash, Tree};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+mod format;
+
+use format::{decode_proof, decode_root, encode_proof, encode_root, Format};
+
+const USAGE: &str = "\
+USAGE:
+    merkle root [--format <hex|json|binary>] [--chunk-size <bytes>] <files...>
+    merkle prove <index> [--format <hex|json|binary>] [--chunk-size <bytes>] <files...>
+    merkle verify [--format <hex|json|binary>] [--leaf-hash] <root> <leaf> <proof>
+
+Every file is a leaf, or a single file is split into chunks with --chunk-size.
+<root> is a hex digest or a file in the selected format, <leaf> a file (or a
+hex digest with --leaf-hash) and <proof> a file in the selected format. Files
+may be `-` for stdin.";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    format: Format,
+    chunk_size: Option<usize>,
+    leaf_hash: bool,
+    args: Vec<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        format: Format::Hex,
+        chunk_size: None,
+        leaf_hash: false,
+        args: vec![],
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                let name = iter.next().ok_or("--format needs a value")?;
+                options.format = Format::parse(name)?;
+            }
+            "--chunk-size" => {
+                let size = iter.next().ok_or("--chunk-size needs a value")?;
+                match size.parse::<usize>() {
+                    Ok(size) if size > 0 => options.chunk_size = Some(size),
+                    _ => return Err(format!("invalid chunk size: {}", size)),
+                }
+            }
+            "--leaf-hash" => options.leaf_hash = true,
+            _ => options.args.push(arg.clone()),
+        }
+    }
+    Ok(options)
+}
+
+/// Hash every file as a leaf, or the chunks of a single file.
+fn leaves(files: &[String], chunk_size: Option<usize>) -> Result<Vec<Hash>, String> {
+    let leaves: Vec<Hash> = match chunk_size {
+        Some(size) => {
+            if files.len() != 1 {
+                return Err("--chunk-size takes exactly one file".to_string());
+            }
+            read(&files[0])?.chunks(size).map(hash_bytes).collect()
+        }
+        None => files
+            .iter()
+            .map(|file| read(file).map(|data| hash_bytes(&data)))
+            .collect::<Result<_, _>>()?,
+    };
+    if leaves.is_empty() {
+        return Err("no leaves to build a tree".to_string());
+    }
+    Ok(leaves)
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    if path == "-" {
+        let mut data = vec![];
+        io::stdin()
+            .read_to_end(&mut data)
+            .map_err(|e| format!("stdin: {}", e))?;
+        return Ok(data);
+    }
+    fs::read(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn build(files: &[String], chunk_size: Option<usize>) -> Result<Tree, String> {
+    let mut tree = Tree::from_hashes(leaves(files, chunk_size)?);
+    tree.build_tree();
+    Ok(tree)
+}
+
+fn root(options: &Options) -> Result<Vec<u8>, String> {
+    let tree = build(&options.args, options.chunk_size)?;
+    Ok(encode_root(&tree.root, options.format))
+}
+
+fn prove(options: &Options) -> Result<Vec<u8>, String> {
+    let (index, files) = options.args.split_first().ok_or(USAGE)?;
+    let index = index
+        .parse::<usize>()
+        .map_err(|_| format!("invalid index: {}", index))?;
+    let tree = build(files, options.chunk_size)?;
+    let proof = tree
+        .get_proof(index)
+        .ok_or(format!("index {} is out of the tree", index))?;
+    Ok(encode_proof(&proof, options.format))
+}
+
+fn verify(options: &Options) -> Result<bool, String> {
+    let (root, leaf, proof) = match options.args.as_slice() {
+        [root, leaf, proof] => (root, leaf, proof),
+        _ => return Err(USAGE.to_string()),
+    };
+    let root = match Hash::from_hex(root) {
+        Some(root) => root,
+        None => decode_root(&read(root)?, options.format)?,
+    };
+    let leaf = if options.leaf_hash {
+        Hash::from_hex(leaf).ok_or(format!("invalid leaf hash: {}", leaf))?
+    } else {
+        hash_bytes(&read(leaf)?)
+    };
+    let proof = decode_proof(&read(proof)?, options.format)?;
+    Ok(proof.verify(&leaf, &root))
+}
+
+fn run(args: &[String]) -> Result<i32, String> {
+    let (command, rest) = args.split_first().ok_or(USAGE)?;
+    let options = parse_options(rest)?;
+    let output = match command.as_str() {
+        "root" => root(&options)?,
+        "prove" => prove(&options)?,
+        "verify" => {
+            if verify(&options)? {
+                println!("OK");
+                return Ok(0);
+            }
+            println!("FAILED");
+            return Ok(1);
+        }
+        _ => return Err(USAGE.to_string()),
+    };
+    io::stdout()
+        .write_all(&output)
+        .map_err(|e| format!("stdout: {}", e))?;
+    Ok(0)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => process::exit(code),
+        Err(message) => {
+            eprintln!("merkle: {}", message);
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merkle_tree::testing::TempDir;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn options() {
+        let options =
+            parse_options(&args(&["--format", "json", "a", "--chunk-size", "4", "b"])).unwrap();
+        assert_eq!(
+            options,
+            Options {
+                format: Format::Json,
+                chunk_size: Some(4),
+                leaf_hash: false,
+                args: args(&["a", "b"]),
+            }
+        );
+        assert!(parse_options(&args(&["--format", "xml"])).is_err());
+        assert!(parse_options(&args(&["--chunk-size", "0"])).is_err());
+    }
+
+    #[test]
+    fn prove_and_verify_chunks() {
+        let dir = TempDir::new("cli");
+        let data = dir.join("data");
+        let chunk = dir.join("chunk");
+        let proof = dir.join("proof");
+        fs::write(&data, b"0123456789").unwrap();
+        fs::write(&chunk, b"4567").unwrap();
+        let data = data.to_str().unwrap();
+
+        let root_file = dir.join("root");
+        let encoded = root(
+            &parse_options(&args(&["--format", "binary", "--chunk-size", "4", data])).unwrap(),
+        )
+        .unwrap();
+        fs::write(&root_file, encoded).unwrap();
+        let encoded = prove(
+            &parse_options(&args(&[
+                "1",
+                "--format",
+                "binary",
+                "--chunk-size",
+                "4",
+                data,
+            ]))
+            .unwrap(),
+        )
+        .unwrap();
+        fs::write(&proof, encoded).unwrap();
+
+        let verify_args = |leaf: &std::path::Path| {
+            args(&[
+                "--format",
+                "binary",
+                root_file.to_str().unwrap(),
+                leaf.to_str().unwrap(),
+                proof.to_str().unwrap(),
+            ])
+        };
+        assert_eq!(
+            verify(&parse_options(&verify_args(&chunk)).unwrap()),
+            Ok(true)
+        );
+        let hex_root = root(&parse_options(&args(&["--chunk-size", "4", data])).unwrap()).unwrap();
+        let mut hex_args = verify_args(&chunk);
+        hex_args[2] = String::from_utf8(hex_root).unwrap().trim().to_string();
+        assert_eq!(verify(&parse_options(&hex_args).unwrap()), Ok(true));
+
+        fs::write(&chunk, b"0123").unwrap();
+        assert_eq!(
+            verify(&parse_options(&verify_args(&chunk)).unwrap()),
+            Ok(false)
+        );
+    }
+}
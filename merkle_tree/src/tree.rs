@@ -0,0 +1,483 @@
+//! Trees, proofs and everything built on them, which need `std`.
+
+use sha2::{Digest, Sha256};
+
+pub mod chunk;
+pub mod dir;
+pub mod document;
+pub mod json;
+pub mod kv;
+pub mod lazy;
+pub mod mac;
+pub mod proof;
+pub mod render;
+pub mod salt;
+pub mod transparency;
+pub mod verkle;
+
+#[cfg(any(test, feature = "test-util"))]
+#[doc(hidden)]
+pub mod testing;
+
+use proof::{binary_proof, kary_proof, KaryProof, MerkleProof};
+use salt::Salt;
+
+#[derive(Debug, Clone, PartialEq, Eq, std::hash::Hash)]
+pub struct Hash {
+    value: String,
+}
+
+impl Hash {
+    fn new(hash: String) -> Hash {
+        Hash { value: hash }
+    }
+
+    /// Build a hash from a raw 32 bytes SHA256 digest.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Hash {
+        Hash::new(hex::encode(bytes))
+    }
+
+    /// Build a hash from a hex encoded SHA256 digest.
+    pub fn from_hex(hex: &str) -> Option<Hash> {
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(hex, &mut bytes).ok()?;
+        Some(Hash::from_bytes(&bytes))
+    }
+
+    /// Return the raw 32 bytes of the digest. Every hash, the root of an empty
+    /// tree included, is a SHA256 digest.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(&self.value, &mut bytes).expect("hash is not a SHA256 hex digest");
+        bytes
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+    parent: Option<Box<Node>>,
+    sibling: Option<Box<Node>>,
+    position: String,
+    data: String,
+    hash: Hash,
+}
+
+impl Node {
+    /// Copy of the node without its links. Sibling and parent links hold
+    /// detached copies so that the size of a tree stays linear.
+    fn detached(&self) -> Node {
+        Node {
+            left: None,
+            right: None,
+            parent: None,
+            sibling: None,
+            position: self.position.clone(),
+            data: self.data.clone(),
+            hash: self.hash.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Tree {
+    pub layers: Vec<Node>,
+    pub root: Hash,
+    levels: Vec<Vec<Hash>>,
+    arity: usize,
+    salts: Vec<Salt>,
+}
+
+impl Tree {
+    pub fn new(data: Vec<&str>) -> Tree {
+        let mut layers: Vec<Node> = Vec::new();
+        for datum in data.clone() {
+            layers.push(Node {
+                left: None,
+                right: None,
+                parent: None,
+                sibling: None,
+                position: "".to_string(),
+                data: datum.to_string(),
+                hash: gen_hash(datum.to_string()),
+            });
+        }
+        Tree {
+            layers,
+            root: empty_root(),
+            levels: vec![],
+            arity: 2,
+            salts: vec![],
+        }
+    }
+
+    /// Create a tree whose leaves are already hashed.
+    pub fn from_hashes(hashes: Vec<Hash>) -> Tree {
+        let layers = hashes
+            .into_iter()
+            .map(|hash| Node {
+                left: None,
+                right: None,
+                parent: None,
+                sibling: None,
+                position: "".to_string(),
+                data: hash.value.clone(),
+                hash,
+            })
+            .collect();
+        Tree {
+            layers,
+            root: empty_root(),
+            levels: vec![],
+            arity: 2,
+            salts: vec![],
+        }
+    }
+
+    /// Set the number of children of every node. Binary trees are the default.
+    pub fn with_arity(mut self, arity: usize) -> Tree {
+        assert!(arity >= 2, "arity must be at least 2");
+        self.arity = arity;
+        self
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn build_tree(&mut self) {
+        if self.arity != 2 {
+            self.build_kary_tree();
+            return;
+        }
+        let mut layers: Vec<Node> = self.layers.clone();
+        if layers.is_empty() {
+            return;
+        }
+        let mut levels = vec![layers.iter().map(|node| node.hash.clone()).collect()];
+        loop {
+            if layers.len() <= 1 {
+                break;
+            }
+            layers = self.build_layers(layers.clone());
+            levels.push(layers.iter().map(|node| node.hash.clone()).collect());
+        }
+        self.layers = layers.clone();
+        self.root = layers[0].hash.clone();
+        self.levels = levels;
+    }
+
+    /// Build a tree whose nodes have up to `arity` children. Only the hashes of
+    /// the levels are kept and `layers` holds a root node without children.
+    fn build_kary_tree(&mut self) {
+        if self.layers.is_empty() {
+            return;
+        }
+        let leaves = self.layers.iter().map(|node| node.hash.clone()).collect();
+        let levels = build_levels(leaves, self.arity, hash_children);
+        if levels.len() > 1 {
+            let children = &levels[levels.len() - 2];
+            let root = &levels[levels.len() - 1][0];
+            self.layers = vec![Node {
+                left: None,
+                right: None,
+                parent: None,
+                sibling: None,
+                position: "".to_string(),
+                data: children.iter().map(|hash| hash.value.as_str()).collect(),
+                hash: root.clone(),
+            }];
+        }
+        self.root = self.layers[0].hash.clone();
+        self.levels = levels;
+    }
+
+    /// Pair up the nodes of a layer. The last node of an odd sized layer is
+    /// promoted to the next layer as it is.
+    fn build_layers(&mut self, layers: Vec<Node>) -> Vec<Node> {
+        let mut new_layers = vec![];
+        for i in (0..(layers.len())).step_by(2) {
+            if i + 1 == layers.len() {
+                new_layers.push(layers[i].clone());
+                break;
+            }
+            let left = &mut layers[i].clone();
+            let right = &mut layers[i + 1].clone();
+
+            left.position = "left".to_string();
+            right.position = "right".to_string();
+            left.sibling = Some(Box::new(right.detached()));
+            right.sibling = Some(Box::new(left.detached()));
+
+            let data = format!("{}{}", left.hash.value, right.hash.value);
+            let hash = gen_hash(data.clone());
+            let mut parent = Node {
+                left: Some(Box::new(left.clone())),
+                right: Some(Box::new(right.clone())),
+                parent: None,
+                sibling: None,
+                position: "".to_string(),
+                data,
+                hash,
+            };
+
+            left.parent = Some(Box::new(parent.detached()));
+            right.parent = Some(Box::new(parent.detached()));
+
+            parent.left = Some(Box::new(left.clone()));
+            parent.right = Some(Box::new(right.clone()));
+
+            new_layers.push(parent);
+        }
+        new_layers
+    }
+
+    fn search(&self, input: String) -> Option<Box<Node>> {
+        dfs(Some(Box::new(self.layers[0].clone())), input)
+    }
+
+    pub fn get_markle_pass(&self, input: String) -> Vec<Vec<String>> {
+        let mut markle_pass: Vec<Vec<String>> = vec![];
+        let node = self.search(input).unwrap();
+        markle_pass.push(vec![
+            node.left.as_ref().unwrap().hash.value.clone(),
+            node.left.as_ref().unwrap().position.clone(),
+        ]);
+        markle_pass.push(vec![
+            node.right.as_ref().unwrap().hash.value.clone(),
+            node.right.as_ref().unwrap().position.clone(),
+        ]);
+        // markle_pass.push(vec![node.hash.value, node.position]);
+        markle_pass.push(vec![
+            node.sibling.as_ref().unwrap().hash.value.clone(),
+            node.sibling.as_ref().unwrap().position.clone(),
+        ]);
+        markle_pass
+    }
+
+    /// Return the inclusion proof of the leaf at `index` in a binary tree.
+    /// `build_tree` must be called before.
+    pub fn get_proof(&self, index: usize) -> Option<MerkleProof> {
+        if self.arity != 2 {
+            return None;
+        }
+        binary_proof(&self.levels, index)
+    }
+
+    /// Return the inclusion proof of the leaf at `index` with all the siblings
+    /// of every level. Works for any arity.
+    /// `build_tree` must be called before.
+    pub fn get_kary_proof(&self, index: usize) -> Option<KaryProof> {
+        kary_proof(&self.levels, self.arity, index)
+    }
+
+    pub fn calc(&self, merkle_pass: Vec<Vec<String>>) -> Hash {
+        let mut ret = Hash {
+            value: merkle_pass[0][0].clone(),
+        };
+        for node in merkle_pass.iter().skip(1) {
+            let val = &node[0];
+            let pos = &node[1];
+            if pos == "left" {
+                ret = gen_hash(format!("{}{}", val, ret.value));
+            } else {
+                ret = gen_hash(format!("{}{}", ret.value, val));
+            }
+        }
+        ret
+    }
+}
+
+/// Return the parent node of the node of the target data.
+fn dfs(node: Option<Box<Node>>, target: String) -> Option<Box<Node>> {
+    match node {
+        Some(obj) => {
+            if obj.left.is_none() && obj.right.is_none() {
+                return None;
+            }
+            let l_ref = obj.left.as_ref().unwrap();
+            let r_ref = obj.right.as_ref().unwrap();
+            if l_ref.data == target || r_ref.data == target {
+                Some(obj)
+            } else {
+                let l_ret = dfs(obj.left, target.clone());
+                let r_ret = dfs(obj.right, target);
+                if l_ret.is_some() {
+                    l_ret
+                } else if r_ret.is_some() {
+                    r_ret
+                } else {
+                    None
+                }
+            }
+        }
+        None => None,
+    }
+}
+
+/// Generate a SHA256 hash of the input string
+pub fn gen_hash(data: String) -> Hash {
+    hash_bytes(data.as_bytes())
+}
+
+/// Generate a SHA256 hash of the input bytes
+pub fn hash_bytes(data: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    Hash::new(hex::encode(hasher.finalize()))
+}
+
+/// Root of a tree without leaves: the hash of no data, as for an empty
+/// directory.
+pub(crate) fn empty_root() -> Hash {
+    hash_bytes(b"")
+}
+
+/// Generate the hash of a parent node from the hashes of its children.
+pub(crate) fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    gen_hash(format!("{}{}", left.value, right.value))
+}
+
+/// Hash the levels of a tree from its leaves up to the root. `combine` hashes
+/// the children of a node.
+pub(crate) fn build_levels(
+    leaves: Vec<Hash>,
+    arity: usize,
+    combine: impl Fn(&[Hash]) -> Hash,
+) -> Vec<Vec<Hash>> {
+    let mut levels = vec![leaves];
+    while levels[levels.len() - 1].len() > 1 {
+        let level = levels[levels.len() - 1]
+            .chunks(arity)
+            .map(&combine)
+            .collect();
+        levels.push(level);
+    }
+    levels
+}
+
+/// Generate the hash of a parent node with any number of children. A single
+/// child is promoted as it is.
+pub(crate) fn hash_children(children: &[Hash]) -> Hash {
+    if children.len() == 1 {
+        return children[0].clone();
+    }
+    gen_hash(children.iter().map(|hash| hash.value.as_str()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gen_hash() {
+        let x = "hello".to_string();
+        let ret = gen_hash(x);
+        assert_eq!(
+            ret.value,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_dfs() {
+        //      root
+        //     /   \
+        //  p1       p2
+        //  / \     / \
+        // a   b   c   d
+        let mut tree = Tree::new(vec!["a", "b", "c", "d"]);
+        tree.build_tree();
+        let p1 = dfs(Some(Box::new(tree.layers[0].clone())), "b".to_string());
+        assert_eq!(p1.unwrap().right.unwrap().data, "b");
+    }
+
+    #[test]
+    fn test_odd_tree() {
+        //      root
+        //     /   \
+        //  p1      c
+        //  / \
+        // a   b
+        let mut tree = Tree::new(vec!["a", "b", "c"]);
+        tree.build_tree();
+        let p1 = hash_pair(&gen_hash("a".to_string()), &gen_hash("b".to_string()));
+        assert_eq!(tree.root, hash_pair(&p1, &gen_hash("c".to_string())));
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let mut tree = Tree::new(vec![]);
+        tree.build_tree();
+        assert_eq!(tree.root, gen_hash("".to_string()));
+        assert_eq!(Hash::from_bytes(&tree.root.to_bytes()), tree.root);
+        let mut kary = Tree::from_hashes(vec![]).with_arity(4);
+        kary.build_tree();
+        assert_eq!(kary.root, tree.root);
+    }
+
+    #[test]
+    fn test_from_hashes() {
+        let data = vec!["a", "b", "c", "d", "e"];
+        let mut tree = Tree::new(data.clone());
+        tree.build_tree();
+        let mut from_hashes = Tree::from_hashes(
+            data.iter()
+                .map(|datum| hash_bytes(datum.as_bytes()))
+                .collect(),
+        );
+        from_hashes.build_tree();
+        assert_eq!(from_hashes.root, tree.root);
+    }
+
+    #[test]
+    fn test_kary_tree() {
+        //         root
+        //      /   |   \
+        //    p1    p2   i
+        //  / | \  / | \
+        // a  b c d  e f
+        let data = vec!["a", "b", "c", "d", "e", "f", "i"];
+        let mut tree = Tree::new(data.clone()).with_arity(3);
+        tree.build_tree();
+        let hashes: Vec<Hash> = data.iter().map(|d| gen_hash(d.to_string())).collect();
+        let p1 = hash_children(&hashes[0..3]);
+        let p2 = hash_children(&hashes[3..6]);
+        assert_eq!(tree.root, hash_children(&[p1, p2, hashes[6].clone()]));
+        assert!(tree.get_proof(0).is_none());
+
+        let mut binary = Tree::new(data);
+        binary.build_tree();
+        let proof = binary.get_kary_proof(4).unwrap();
+        assert_eq!(proof.root(&hashes[4]), Some(binary.root));
+    }
+
+    #[test]
+    fn test_get_proof() {
+        let data = vec!["a", "b", "c", "d", "e"];
+        let mut tree = Tree::new(data.clone());
+        tree.build_tree();
+        for (i, datum) in data.iter().enumerate() {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(proof.verify(&gen_hash(datum.to_string()), &tree.root));
+        }
+        assert!(tree.get_proof(5).is_none());
+    }
+}
+
+// 実行例
+// let data = vec!["a", "b", "c", "d"];
+// let mut tree = Tree::new(data);
+// tree.build_tree();
+// let merkle_pass = tree.get_markle_pass("b".to_string());
+// for pass in &merkle_pass {
+//     println!("Hash: {}, Pos: {}", pass[0], pass[1]);
+// }
+// let hash = tree.calc(merkle_pass);
+// println!("Expected: {:?}", tree.root);
+// println!("Output: {:?}", hash);
@@ -0,0 +1,225 @@
+use crate::{hash_bytes, Hash, Tree};
+use std::collections::HashMap;
+
+/// Random values of the Gear rolling hash, generated by splitmix64 from a
+/// fixed seed so that chunk boundaries are stable across builds.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x6d65_726b_6c65_5f63;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Mask of the `bits` highest bits. The high bits of the Gear hash depend on
+/// the most bytes of the window.
+fn high_mask(bits: u32) -> u64 {
+    (u64::MAX >> (64 - bits)) << (64 - bits)
+}
+
+/// Content-defined chunker in the style of FastCDC. A boundary is cut where
+/// the rolling hash matches a mask: a harder one before `avg_size` and an
+/// easier one after, which keeps the chunk sizes close to the average.
+#[derive(Debug, Clone)]
+pub struct Chunker {
+    min_size: usize,
+    avg_size: usize,
+    max_size: usize,
+    mask_small: u64,
+    mask_large: u64,
+}
+
+impl Chunker {
+    pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Chunker {
+        assert!(
+            0 < min_size && min_size <= avg_size && avg_size <= max_size,
+            "chunk sizes must be 0 < min <= avg <= max"
+        );
+        assert!(avg_size >= 4, "average chunk size must be at least 4");
+        let bits = usize::BITS - avg_size.leading_zeros() - 1;
+        Chunker {
+            min_size,
+            avg_size,
+            max_size,
+            mask_small: high_mask(bits + 1),
+            mask_large: high_mask(bits - 1),
+        }
+    }
+
+    /// Length of the first chunk of `data`.
+    fn cut(&self, data: &[u8]) -> usize {
+        if data.len() <= self.min_size {
+            return data.len();
+        }
+        let end = data.len().min(self.max_size);
+        let normal = end.min(self.avg_size);
+        let mut hash: u64 = 0;
+        let mut i = self.min_size;
+        while i < normal {
+            hash = (hash << 1).wrapping_add(GEAR[data[i] as usize]);
+            if hash & self.mask_small == 0 {
+                return i + 1;
+            }
+            i += 1;
+        }
+        while i < end {
+            hash = (hash << 1).wrapping_add(GEAR[data[i] as usize]);
+            if hash & self.mask_large == 0 {
+                return i + 1;
+            }
+            i += 1;
+        }
+        end
+    }
+
+    /// Split `data` into chunks.
+    pub fn chunks<'a>(&self, data: &'a [u8]) -> Vec<&'a [u8]> {
+        let mut ret = vec![];
+        let mut rest = data;
+        while !rest.is_empty() {
+            let (chunk, tail) = rest.split_at(self.cut(rest));
+            ret.push(chunk);
+            rest = tail;
+        }
+        ret
+    }
+}
+
+/// Chunks of a file and the root of the tree over their hashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileManifest {
+    pub root: Hash,
+    pub chunks: Vec<Hash>,
+}
+
+/// Store keeping every unique chunk once across many files.
+#[derive(Debug)]
+pub struct ChunkStore {
+    chunker: Chunker,
+    chunks: HashMap<Hash, Vec<u8>>,
+}
+
+impl ChunkStore {
+    pub fn new(chunker: Chunker) -> ChunkStore {
+        ChunkStore {
+            chunker,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Chunk `data`, store the chunks not seen yet and return the manifest of
+    /// the file.
+    pub fn add_file(&mut self, data: &[u8]) -> FileManifest {
+        let mut hashes = vec![];
+        for chunk in self.chunker.chunks(data) {
+            let hash = hash_bytes(chunk);
+            self.chunks
+                .entry(hash.clone())
+                .or_insert_with(|| chunk.to_vec());
+            hashes.push(hash);
+        }
+        let mut tree = Tree::from_hashes(hashes.clone());
+        tree.build_tree();
+        FileManifest {
+            root: tree.root,
+            chunks: hashes,
+        }
+    }
+
+    pub fn get(&self, hash: &Hash) -> Option<&[u8]> {
+        self.chunks.get(hash).map(Vec::as_slice)
+    }
+
+    /// Rebuild the content of a file from its manifest.
+    pub fn read_file(&self, manifest: &FileManifest) -> Option<Vec<u8>> {
+        let mut data = vec![];
+        for hash in &manifest.chunks {
+            data.extend_from_slice(self.get(hash)?);
+        }
+        Some(data)
+    }
+
+    /// Number of unique chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Number of bytes of the unique chunks.
+    pub fn stored_bytes(&self) -> usize {
+        self.chunks.values().map(Vec::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{RngCore, SeedableRng};
+
+    fn random_data(seed: u64, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        StdRng::seed_from_u64(seed).fill_bytes(&mut data);
+        data
+    }
+
+    #[test]
+    fn chunk_sizes() {
+        let chunker = Chunker::new(256, 1024, 4096);
+        let data = random_data(1, 200_000);
+        let chunks = chunker.chunks(&data);
+        assert_eq!(chunks.concat(), data);
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!((256..=4096).contains(&chunk.len()));
+        }
+        let avg = data.len() / chunks.len();
+        assert!((512..=2048).contains(&avg), "average chunk size {}", avg);
+        assert!(chunker.chunks(&[]).is_empty());
+        assert_eq!(chunker.chunks(&data[..100]), vec![&data[..100]]);
+    }
+
+    #[test]
+    fn insertion_changes_few_chunks() {
+        let mut store = ChunkStore::new(Chunker::new(256, 1024, 4096));
+        let data = random_data(2, 100_000);
+        let mut edited = data.clone();
+        edited.splice(1000..1000, b"inserted bytes".iter().copied());
+
+        let original = store.add_file(&data);
+        let unique = store.len();
+        let modified = store.add_file(&edited);
+        assert_ne!(original.root, modified.root);
+        assert!(
+            store.len() - unique <= 3,
+            "{} new chunks",
+            store.len() - unique
+        );
+        assert_eq!(store.read_file(&original), Some(data.clone()));
+        assert_eq!(store.read_file(&modified), Some(edited));
+
+        let stored = store.stored_bytes();
+        assert_eq!(store.add_file(&data), original);
+        assert_eq!(store.stored_bytes(), stored);
+    }
+
+    #[test]
+    fn empty_file() {
+        let mut store = ChunkStore::new(Chunker::new(256, 1024, 4096));
+        let manifest = store.add_file(&[]);
+        assert_eq!(manifest.root, crate::empty_root());
+        assert!(manifest.chunks.is_empty());
+        assert_eq!(store.read_file(&manifest), Some(vec![]));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{empty_root, gen_hash, hash_bytes, Hash, Tree};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Node of the Merkle DAG of a directory tree.
+/// Every directory has its own root built from its entries in sorted order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirNode {
+    File {
+        hash: Hash,
+    },
+    Symlink {
+        hash: Hash,
+    },
+    Dir {
+        hash: Hash,
+        children: BTreeMap<String, DirNode>,
+    },
+}
+
+/// Paths that differ between two directory trees, relative to their roots.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangeReport {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl DirNode {
+    pub fn hash(&self) -> &Hash {
+        match self {
+            DirNode::File { hash } | DirNode::Symlink { hash } | DirNode::Dir { hash, .. } => hash,
+        }
+    }
+
+    fn kind(&self) -> &str {
+        match self {
+            DirNode::File { .. } => "file",
+            DirNode::Symlink { .. } => "link",
+            DirNode::Dir { .. } => "dir",
+        }
+    }
+
+    /// Return the root of every directory, keyed by its relative path.
+    /// The top directory is `""`.
+    pub fn roots(&self) -> Vec<(String, Hash)> {
+        let mut ret = vec![];
+        collect_roots(self, "", &mut ret);
+        ret
+    }
+}
+
+fn collect_roots(node: &DirNode, path: &str, ret: &mut Vec<(String, Hash)>) {
+    if let DirNode::Dir { hash, children } = node {
+        ret.push((path.to_string(), hash.clone()));
+        for (name, child) in children {
+            collect_roots(child, &join(path, name), ret);
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", path, name)
+    }
+}
+
+/// Walk the directory at `path` and build its Merkle DAG.
+/// A leaf of a directory is the hash of the kind, the name relative to the
+/// directory and the hash of the entry, so renaming or moving a file changes
+/// the roots up to the top. Symlinks are hashed by their target and not followed.
+pub fn hash_dir(path: &Path) -> io::Result<DirNode> {
+    let mut children = BTreeMap::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name().into_string().map_err(|name| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("file name is not UTF-8: {:?}", name),
+            )
+        })?;
+        let file_type = entry.file_type()?;
+        let node = if file_type.is_dir() {
+            hash_dir(&entry.path())?
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(entry.path())?;
+            DirNode::Symlink {
+                hash: gen_hash(target.to_string_lossy().to_string()),
+            }
+        } else {
+            DirNode::File {
+                hash: hash_bytes(&fs::read(entry.path())?),
+            }
+        };
+        children.insert(name, node);
+    }
+    Ok(DirNode::Dir {
+        hash: dir_root(&children),
+        children,
+    })
+}
+
+/// Root of a directory from its sorted entries. An empty directory has the
+/// root of an empty tree.
+fn dir_root(children: &BTreeMap<String, DirNode>) -> Hash {
+    if children.is_empty() {
+        return empty_root();
+    }
+    let leaves = children
+        .iter()
+        .map(|(name, node)| gen_hash(format!("{}:{}:{}", node.kind(), name, node.hash().value)))
+        .collect();
+    let mut tree = Tree::from_hashes(leaves);
+    tree.build_tree();
+    tree.root
+}
+
+/// Compare two directory trees. Only the subdirectories whose roots differ are
+/// visited.
+pub fn diff(old: &DirNode, new: &DirNode) -> ChangeReport {
+    let mut report = ChangeReport::default();
+    diff_node(old, new, "", &mut report);
+    report
+}
+
+fn diff_node(old: &DirNode, new: &DirNode, path: &str, report: &mut ChangeReport) {
+    if old.hash() == new.hash() && old.kind() == new.kind() {
+        return;
+    }
+    match (old, new) {
+        (
+            DirNode::Dir {
+                children: old_children,
+                ..
+            },
+            DirNode::Dir {
+                children: new_children,
+                ..
+            },
+        ) => {
+            for (name, old_child) in old_children {
+                let child_path = join(path, name);
+                match new_children.get(name) {
+                    Some(new_child) => diff_node(old_child, new_child, &child_path, report),
+                    None => report.removed.push(child_path),
+                }
+            }
+            for name in new_children.keys() {
+                if !old_children.contains_key(name) {
+                    report.added.push(join(path, name));
+                }
+            }
+        }
+        _ if old.kind() != new.kind() => {
+            report.removed.push(path.to_string());
+            report.added.push(path.to_string());
+        }
+        _ => report.modified.push(path.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn sample_dir(name: &str) -> TempDir {
+        let dir = TempDir::new(&format!("dir_{}", name));
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::write(dir.join("README.md"), "readme").unwrap();
+        fs::write(dir.join("src/lib.rs"), "lib").unwrap();
+        fs::write(dir.join("src/nested/mod.rs"), "mod").unwrap();
+        fs::write(dir.join("docs/guide.md"), "guide").unwrap();
+        dir
+    }
+
+    #[test]
+    fn deterministic_roots() {
+        let a = sample_dir("a");
+        let b = sample_dir("b");
+        let a_node = hash_dir(&a).unwrap();
+        assert_eq!(a_node, hash_dir(&b).unwrap());
+        let roots: Vec<String> = a_node.roots().into_iter().map(|(path, _)| path).collect();
+        assert_eq!(roots, vec!["", "docs", "src", "src/nested"]);
+
+        fs::rename(b.join("docs/guide.md"), b.join("docs/howto.md")).unwrap();
+        assert_ne!(a_node.hash(), hash_dir(&b).unwrap().hash());
+    }
+
+    #[test]
+    fn empty_dir() {
+        let dir = TempDir::new("dir_empty");
+        assert_eq!(hash_dir(&dir).unwrap().hash(), &empty_root());
+    }
+
+    #[test]
+    fn change_report() {
+        let dir = sample_dir("diff");
+        let old = hash_dir(&dir).unwrap();
+        fs::write(dir.join("src/nested/mod.rs"), "changed").unwrap();
+        fs::remove_file(dir.join("README.md")).unwrap();
+        fs::write(dir.join("src/main.rs"), "main").unwrap();
+        fs::remove_dir_all(dir.join("docs")).unwrap();
+        fs::write(dir.join("docs"), "now a file").unwrap();
+        let new = hash_dir(&dir).unwrap();
+
+        assert_eq!(
+            diff(&old, &new),
+            ChangeReport {
+                added: vec!["docs".to_string(), "src/main.rs".to_string()],
+                removed: vec!["README.md".to_string(), "docs".to_string()],
+                modified: vec!["src/nested/mod.rs".to_string()],
+            }
+        );
+        assert_eq!(diff(&new, &new), ChangeReport::default());
+    }
+}
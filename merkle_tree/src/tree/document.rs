@@ -0,0 +1,389 @@
+use crate::kv::entry_hash;
+use crate::proof::{binary_proof, MerkleProof};
+use crate::salt::{salted_hash, Salt};
+use crate::{build_levels, gen_hash, hash_children, Hash};
+use scrach_json_parser::{canonical, Number, Value};
+use std::fmt;
+
+/// One step of a field path: `.key` or `[index]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    InvalidPath(String),
+    NotFound(String),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::InvalidPath(path) => write!(f, "invalid path: {}", path),
+            PathError::NotFound(path) => write!(f, "no value at path: {}", path),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Parse a path such as `$.user.emails[0]`.
+pub fn parse_path(path: &str) -> Result<Vec<Segment>, PathError> {
+    let invalid = || PathError::InvalidPath(path.to_string());
+    let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut segments = vec![];
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix('.') {
+            let end = tail.find(['.', '[']).unwrap_or(tail.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(Segment::Key(tail[..end].to_string()));
+            rest = &tail[end..];
+        } else if let Some(tail) = rest.strip_prefix('[') {
+            let end = tail.find(']').ok_or_else(invalid)?;
+            let index = tail[..end].parse::<usize>().map_err(|_| invalid())?;
+            segments.push(Segment::Index(index));
+            rest = &tail[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+/// Number of salts needed to commit to `value`: one per scalar or empty
+/// container in it, the leaves of the document.
+pub fn salt_count(value: &Value) -> usize {
+    match value {
+        Value::Object(object) if !object.is_empty() => object.values().map(salt_count).sum(),
+        Value::Array(array) if !array.is_empty() => array.iter().map(salt_count).sum(),
+        _ => 1,
+    }
+}
+
+/// Split `salts` between the children of a container.
+fn child_salts<'a>(
+    children: impl Iterator<Item = &'a Value>,
+    salts: &'a [Salt],
+) -> Vec<(&'a Value, &'a [Salt])> {
+    let mut start = 0;
+    children
+        .map(|child| {
+            let end = start + salt_count(child);
+            let ret = (child, &salts[start..end]);
+            start = end;
+            ret
+        })
+        .collect()
+}
+
+/// Hash a leaf of the document with a tag of its type, so that `"1"` and `1`
+/// differ, and with its salt, so that it cannot be guessed from its hash.
+fn leaf_hash(tag: &str, text: &str, salt: &Salt) -> Hash {
+    salted_hash(&format!("{}:{}", tag, text), salt)
+}
+
+/// Text committed for a number: its canonical JSON form, so that `1.5` and
+/// `1.50` commit alike whatever the parse mode. Numbers canonical JSON cannot
+/// hold are committed as written.
+fn number_text(n: &Number) -> String {
+    canonical::to_string(&Value::Number(n.clone())).unwrap_or_else(|_| n.to_string())
+}
+
+/// Levels of the tree of the children of a container.
+fn container_levels(value: &Value, salts: &[Salt]) -> Vec<Vec<Hash>> {
+    let leaves: Vec<Hash> = match value {
+        Value::Object(object) => child_salts(object.sorted().map(|(_, value)| value), salts)
+            .into_iter()
+            .zip(object.sorted().map(|(key, _)| key))
+            .map(|((value, salts), key)| entry_hash(key, json_root(value, salts).value()))
+            .collect(),
+        Value::Array(array) => child_salts(array.iter(), salts)
+            .into_iter()
+            .map(|(value, salts)| json_root(value, salts))
+            .collect(),
+        _ => vec![],
+    };
+    if leaves.is_empty() {
+        return vec![];
+    }
+    build_levels(leaves, 2, hash_children)
+}
+
+/// Hash of a non-empty container from the root of the tree of its children.
+fn container_hash(tag: &str, root: &Hash) -> Hash {
+    gen_hash(format!("{}:{}", tag, root.value()))
+}
+
+/// Commit to a JSON value structurally. Objects are trees of their key-value
+/// pairs sorted by key, arrays trees of their elements. Scalars and empty
+/// containers are hashed with their type and one of `salts`, taken in the
+/// order of the leaves, object members by key; `salts` must hold
+/// `salt_count(value)` of them.
+pub fn json_root(value: &Value, salts: &[Salt]) -> Hash {
+    assert_eq!(
+        salts.len(),
+        salt_count(value),
+        "one salt is needed per leaf"
+    );
+    match value {
+        Value::Null => leaf_hash("null", "", &salts[0]),
+        Value::Boolean(b) => leaf_hash("bool", &b.to_string(), &salts[0]),
+        Value::Number(n) => leaf_hash("number", &number_text(n), &salts[0]),
+        Value::String(s) => leaf_hash("string", s, &salts[0]),
+        Value::Object(object) if object.is_empty() => leaf_hash("object", "", &salts[0]),
+        Value::Array(array) if array.is_empty() => leaf_hash("array", "", &salts[0]),
+        Value::Object(_) => {
+            let levels = container_levels(value, salts);
+            container_hash("object", &levels[levels.len() - 1][0])
+        }
+        Value::Array(_) => {
+            let levels = container_levels(value, salts);
+            container_hash("array", &levels[levels.len() - 1][0])
+        }
+    }
+}
+
+/// Proof of the value of one field. `steps` go from the document root down
+/// to the field; only the hashes of the other fields are revealed. `salts`
+/// are the salts of the proven value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldProof {
+    pub steps: Vec<(Segment, MerkleProof)>,
+    pub salts: Vec<Salt>,
+}
+
+/// Prove the value at `path` of `document` committed with `salts`.
+pub fn prove_field(
+    document: &Value,
+    salts: &[Salt],
+    path: &str,
+) -> Result<(Value, FieldProof), PathError> {
+    assert_eq!(
+        salts.len(),
+        salt_count(document),
+        "one salt is needed per leaf"
+    );
+    let not_found = || PathError::NotFound(path.to_string());
+    let mut steps = vec![];
+    let mut value = document;
+    let mut salts = salts;
+    for segment in parse_path(path)? {
+        let (index, children) = match (&segment, value) {
+            (Segment::Key(key), Value::Object(object)) => (
+                object
+                    .sorted()
+                    .position(|(k, _)| k == key)
+                    .ok_or_else(not_found)?,
+                child_salts(object.sorted().map(|(_, value)| value), salts),
+            ),
+            (Segment::Index(index), Value::Array(array)) => {
+                (*index, child_salts(array.iter(), salts))
+            }
+            _ => return Err(not_found()),
+        };
+        let proof = binary_proof(&container_levels(value, salts), index).ok_or_else(not_found)?;
+        steps.push((segment, proof));
+        (value, salts) = children[index];
+    }
+    Ok((
+        value.clone(),
+        FieldProof {
+            steps,
+            salts: salts.to_vec(),
+        },
+    ))
+}
+
+impl FieldProof {
+    /// Check that `value` is at `path` of the document committed by `root`.
+    pub fn verify(&self, path: &str, value: &Value, root: &Hash) -> bool {
+        let segments = match parse_path(path) {
+            Ok(segments) => segments,
+            Err(_) => return false,
+        };
+        if segments.len() != self.steps.len()
+            || segments
+                .iter()
+                .zip(&self.steps)
+                .any(|(segment, (step, _))| segment != step)
+            || self.salts.len() != salt_count(value)
+        {
+            return false;
+        }
+        let mut hash = json_root(value, &self.salts);
+        for (segment, proof) in self.steps.iter().rev() {
+            if !proof.is_well_formed() {
+                return false;
+            }
+            hash = match segment {
+                Segment::Key(key) => {
+                    container_hash("object", &proof.root(&entry_hash(key, hash.value())))
+                }
+                Segment::Index(index) => {
+                    if proof.index != *index {
+                        return false;
+                    }
+                    container_hash("array", &proof.root(&hash))
+                }
+            };
+        }
+        &hash == root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::salt::seeded_salts;
+
+    fn parse(json: &str) -> Value {
+        scrach_json_parser::from_str(json).unwrap()
+    }
+
+    fn document() -> Value {
+        parse(
+            r#"{
+              "user": {"name": "Yuhei", "email": "yuhei@example.com", "age": 32},
+              "tags": ["a", "b", "c"],
+              "active": true
+            }"#,
+        )
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            parse_path("$.user.emails[10]"),
+            Ok(vec![
+                Segment::Key("user".to_string()),
+                Segment::Key("emails".to_string()),
+                Segment::Index(10),
+            ])
+        );
+        assert_eq!(parse_path("$"), Ok(vec![]));
+        for path in ["user", "$.", "$..a", "$[x]", "$[1", "$a"] {
+            assert_eq!(
+                parse_path(path),
+                Err(PathError::InvalidPath(path.to_string()))
+            );
+        }
+    }
+
+    fn salts(value: &Value) -> Vec<Salt> {
+        seeded_salts(7, salt_count(value))
+    }
+
+    #[test]
+    fn type_tagged() {
+        let salt = seeded_salts(7, 1);
+        assert_ne!(
+            json_root(&Value::String("1".to_string()), &salt),
+            json_root(&Value::Number(1u64.into()), &salt)
+        );
+        assert_ne!(
+            json_root(&Value::Array(vec![]), &salt),
+            json_root(&Value::Object(Default::default()), &salt)
+        );
+        assert_ne!(
+            json_root(&parse("[[]]"), &salt),
+            json_root(&parse("[]"), &salt)
+        );
+        assert_eq!(salt_count(&document()), 7);
+    }
+
+    #[test]
+    fn number_forms() {
+        let salt = seeded_salts(7, 1);
+        let precise = scrach_json_parser::from_str_arbitrary_precision;
+        let roots: Vec<Hash> = [
+            parse("1.5"),
+            parse("1.50"),
+            precise("1.5").unwrap(),
+            precise("1.50").unwrap(),
+            precise("15e-1").unwrap(),
+        ]
+        .iter()
+        .map(|value| json_root(value, &salt))
+        .collect();
+        assert!(roots.iter().all(|root| root == &roots[0]));
+        assert_ne!(json_root(&parse("1.25"), &salt), roots[0]);
+    }
+
+    #[test]
+    fn member_order() {
+        let document = document();
+        let reordered = parse(
+            r#"{
+              "active": true,
+              "tags": ["a", "b", "c"],
+              "user": {"age": 32, "email": "yuhei@example.com", "name": "Yuhei"}
+            }"#,
+        );
+        let salts = salts(&document);
+        assert_eq!(json_root(&document, &salts), json_root(&reordered, &salts));
+    }
+
+    #[test]
+    fn prove_fields() {
+        let document = document();
+        let salts = salts(&document);
+        let root = json_root(&document, &salts);
+        let (value, proof) = prove_field(&document, &salts, "$.user.email").unwrap();
+        assert_eq!(value, Value::String("yuhei@example.com".to_string()));
+        assert!(proof.verify("$.user.email", &value, &root));
+        assert!(!proof.verify("$.user.name", &value, &root));
+        assert!(!proof.verify(
+            "$.user.email",
+            &Value::String("eve@example.com".to_string()),
+            &root
+        ));
+
+        let (value, proof) = prove_field(&document, &salts, "$.tags[1]").unwrap();
+        assert_eq!(value, Value::String("b".to_string()));
+        assert!(proof.verify("$.tags[1]", &value, &root));
+
+        let (value, proof) = prove_field(&document, &salts, "$").unwrap();
+        assert!(proof.verify("$", &value, &root));
+
+        assert_eq!(
+            prove_field(&document, &salts, "$.user.phone"),
+            Err(PathError::NotFound("$.user.phone".to_string()))
+        );
+        assert_eq!(
+            prove_field(&document, &salts, "$.tags[3]"),
+            Err(PathError::NotFound("$.tags[3]".to_string()))
+        );
+    }
+
+    #[test]
+    fn other_fields_stay_hidden() {
+        let document = document();
+        let salts = salts(&document);
+        let root = json_root(&document, &salts);
+        let (_, proof) = prove_field(&document, &salts, "$.user.email").unwrap();
+        let revealed: Vec<&Hash> = proof
+            .steps
+            .iter()
+            .flat_map(|(_, proof)| proof.path.iter().map(|node| &node.hash))
+            .collect();
+        let guess = |salt: &Salt| {
+            (0..200u64).any(|age| {
+                let hash = json_root(&Value::Number(age.into()), &[*salt]);
+                revealed.contains(&&entry_hash("age", hash.value()))
+            })
+        };
+        // "age" is the fifth leaf of the document: knowing its salt, the
+        // guess works, so only the salt keeps the age hidden.
+        assert!(guess(&salts[4]));
+        assert!(!guess(&proof.salts[0]));
+        assert!(!guess(&[0u8; 32]));
+
+        let mut wrong_salt = proof;
+        wrong_salt.salts = seeded_salts(8, 1);
+        assert!(!wrong_salt.verify("$.user.email", &parse(r#""yuhei@example.com""#), &root));
+    }
+}
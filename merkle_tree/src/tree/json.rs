@@ -0,0 +1,427 @@
+use crate::proof::{MerkleProof, Position, ProofNode};
+use crate::transparency::{SignedTreeHead, TreeHead};
+use crate::{Hash, Tree};
+use ed25519_dalek::Signature;
+use scrach_json_parser::{Map, Number, Value};
+use std::fmt;
+
+/// Name of the hash algorithm written in every document.
+pub const ALGORITHM: &str = "sha256";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonError {
+    NotAnObject(&'static str),
+    MissingField(&'static str),
+    InvalidType {
+        field: &'static str,
+        expected: &'static str,
+    },
+    InvalidHash(String),
+    InvalidPosition(String),
+    InvalidSignature(String),
+    UnsupportedAlgorithm(String),
+    RootMismatch,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonError::NotAnObject(name) => write!(f, "{} must be an object", name),
+            JsonError::MissingField(field) => write!(f, "missing field: {}", field),
+            JsonError::InvalidType { field, expected } => {
+                write!(f, "{} must be {}", field, expected)
+            }
+            JsonError::InvalidHash(hash) => write!(f, "invalid hash: {}", hash),
+            JsonError::InvalidPosition(position) => {
+                write!(f, "position must be \"left\" or \"right\": {}", position)
+            }
+            JsonError::InvalidSignature(signature) => write!(f, "invalid signature: {}", signature),
+            JsonError::UnsupportedAlgorithm(name) => write!(f, "unsupported algorithm: {}", name),
+            JsonError::RootMismatch => write!(f, "root does not match the leaves"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+fn as_object<'a>(value: &'a Value, name: &'static str) -> Result<&'a Map, JsonError> {
+    match value {
+        Value::Object(object) => Ok(object),
+        _ => Err(JsonError::NotAnObject(name)),
+    }
+}
+
+fn field<'a>(object: &'a Map, field: &'static str) -> Result<&'a Value, JsonError> {
+    object.get(field).ok_or(JsonError::MissingField(field))
+}
+
+fn string_field<'a>(object: &'a Map, name: &'static str) -> Result<&'a str, JsonError> {
+    match field(object, name)? {
+        Value::String(s) => Ok(s),
+        _ => Err(JsonError::InvalidType {
+            field: name,
+            expected: "a string",
+        }),
+    }
+}
+
+fn integer_field(object: &Map, name: &'static str) -> Result<u64, JsonError> {
+    match field(object, name)? {
+        Value::Number(n) => n.as_u64(),
+        _ => None,
+    }
+    .ok_or(JsonError::InvalidType {
+        field: name,
+        expected: "a non-negative integer",
+    })
+}
+
+fn usize_field(object: &Map, name: &'static str) -> Result<usize, JsonError> {
+    usize::try_from(integer_field(object, name)?).map_err(|_| JsonError::InvalidType {
+        field: name,
+        expected: "a non-negative integer",
+    })
+}
+
+fn array_field<'a>(object: &'a Map, name: &'static str) -> Result<&'a [Value], JsonError> {
+    match field(object, name)? {
+        Value::Array(array) => Ok(array),
+        _ => Err(JsonError::InvalidType {
+            field: name,
+            expected: "an array",
+        }),
+    }
+}
+
+fn parse_hash(value: &Value, name: &'static str) -> Result<Hash, JsonError> {
+    match value {
+        Value::String(hex) => {
+            Hash::from_hex(hex).ok_or_else(|| JsonError::InvalidHash(hex.clone()))
+        }
+        _ => Err(JsonError::InvalidType {
+            field: name,
+            expected: "a hex digest",
+        }),
+    }
+}
+
+fn check_algorithm(object: &Map) -> Result<(), JsonError> {
+    let algorithm = string_field(object, "algorithm")?;
+    if algorithm != ALGORITHM {
+        return Err(JsonError::UnsupportedAlgorithm(algorithm.to_string()));
+    }
+    Ok(())
+}
+
+fn number(n: usize) -> Value {
+    Value::Number(Number::from(n as u64))
+}
+
+fn hash_value(hash: &Hash) -> Value {
+    Value::String(hash.value().to_string())
+}
+
+/// Object of `fields` after the algorithm.
+fn document(fields: Vec<(&str, Value)>) -> Value {
+    let algorithm = ("algorithm", Value::String(ALGORITHM.to_string()));
+    Value::Object(
+        std::iter::once(algorithm)
+            .chain(fields)
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+/// `{"algorithm", "root"}`, a root published on its own.
+pub fn root_to_json(root: &Hash) -> Value {
+    document(vec![("root", hash_value(root))])
+}
+
+pub fn root_from_json(value: &Value) -> Result<Hash, JsonError> {
+    let object = as_object(value, "root")?;
+    check_algorithm(object)?;
+    parse_hash(field(object, "root")?, "root")
+}
+
+impl MerkleProof {
+    /// `{"algorithm", "tree_size", "index", "path": [{"hash", "position"}]}`
+    pub fn to_json(&self) -> Value {
+        let path = self
+            .path
+            .iter()
+            .map(|node| {
+                let mut object = Map::new();
+                object.insert("hash".to_string(), hash_value(&node.hash));
+                let position = match node.position {
+                    Position::Left => "left",
+                    Position::Right => "right",
+                };
+                object.insert("position".to_string(), Value::String(position.to_string()));
+                Value::Object(object)
+            })
+            .collect();
+        document(vec![
+            ("tree_size", number(self.tree_size)),
+            ("index", number(self.index)),
+            ("path", Value::Array(path)),
+        ])
+    }
+
+    pub fn from_json(value: &Value) -> Result<MerkleProof, JsonError> {
+        let object = as_object(value, "proof")?;
+        check_algorithm(object)?;
+        let tree_size = usize_field(object, "tree_size")?;
+        let index = usize_field(object, "index")?;
+        let mut path = vec![];
+        for node in array_field(object, "path")? {
+            let node = as_object(node, "path entry")?;
+            let position = match string_field(node, "position")? {
+                "left" => Position::Left,
+                "right" => Position::Right,
+                position => return Err(JsonError::InvalidPosition(position.to_string())),
+            };
+            path.push(ProofNode {
+                hash: parse_hash(field(node, "hash")?, "hash")?,
+                position,
+            });
+        }
+        Ok(MerkleProof {
+            tree_size,
+            index,
+            path,
+        })
+    }
+}
+
+impl TreeHead {
+    /// `{"algorithm", "tree_size", "root", "timestamp"}`
+    pub fn to_json(&self) -> Value {
+        document(vec![
+            ("tree_size", number(self.tree_size)),
+            ("root", hash_value(&self.root)),
+            ("timestamp", Value::Number(Number::from(self.timestamp))),
+        ])
+    }
+
+    pub fn from_json(value: &Value) -> Result<TreeHead, JsonError> {
+        let object = as_object(value, "tree head")?;
+        check_algorithm(object)?;
+        Ok(TreeHead {
+            tree_size: usize_field(object, "tree_size")?,
+            root: parse_hash(field(object, "root")?, "root")?,
+            timestamp: integer_field(object, "timestamp")?,
+        })
+    }
+}
+
+impl SignedTreeHead {
+    /// The fields of the tree head with a hex `signature`.
+    pub fn to_json(&self) -> Value {
+        let mut value = self.head.to_json();
+        if let Value::Object(object) = &mut value {
+            object.insert(
+                "signature".to_string(),
+                Value::String(hex::encode(self.signature.to_bytes())),
+            );
+        }
+        value
+    }
+
+    /// Parse a signed tree head. The signature is not checked.
+    pub fn from_json(value: &Value) -> Result<SignedTreeHead, JsonError> {
+        let head = TreeHead::from_json(value)?;
+        let signature = string_field(as_object(value, "tree head")?, "signature")?;
+        let bytes: [u8; 64] = hex::decode(signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| JsonError::InvalidSignature(signature.to_string()))?;
+        Ok(SignedTreeHead {
+            head,
+            signature: Signature::from_bytes(&bytes),
+        })
+    }
+}
+
+impl Tree {
+    /// `{"algorithm", "arity", "leaves", "root"}` with the hashes of the
+    /// leaves. Meant for small trees as every leaf is written.
+    pub fn to_json(&self) -> Value {
+        let leaves = match self.levels.first() {
+            Some(leaves) => leaves.iter().map(hash_value).collect(),
+            None => self
+                .layers
+                .iter()
+                .map(|node| hash_value(&node.hash))
+                .collect(),
+        };
+        document(vec![
+            ("arity", number(self.arity)),
+            ("leaves", Value::Array(leaves)),
+            ("root", hash_value(&self.root)),
+        ])
+    }
+
+    /// Rebuild a tree from its leaves and check it against the root.
+    pub fn from_json(value: &Value) -> Result<Tree, JsonError> {
+        let object = as_object(value, "tree")?;
+        check_algorithm(object)?;
+        let arity = usize_field(object, "arity")?;
+        if arity < 2 {
+            return Err(JsonError::InvalidType {
+                field: "arity",
+                expected: "at least 2",
+            });
+        }
+        let leaves = array_field(object, "leaves")?
+            .iter()
+            .map(|leaf| parse_hash(leaf, "leaves"))
+            .collect::<Result<Vec<Hash>, JsonError>>()?;
+        let root = parse_hash(field(object, "root")?, "root")?;
+        let mut tree = Tree::from_hashes(leaves).with_arity(arity);
+        tree.build_tree();
+        if tree.root != root {
+            return Err(JsonError::RootMismatch);
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen_hash;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn parse(json: &str) -> Value {
+        scrach_json_parser::from_str(json).unwrap()
+    }
+
+    fn tree() -> Tree {
+        let mut tree = Tree::new(vec!["a", "b", "c", "d", "e"]);
+        tree.build_tree();
+        tree
+    }
+
+    #[test]
+    fn proof_round_trip() {
+        let proof = tree().get_proof(3).unwrap();
+        let value = proof.to_json();
+        assert_eq!(MerkleProof::from_json(&value), Ok(proof));
+
+        let object = as_object(&value, "proof").unwrap();
+        assert_eq!(object["algorithm"], Value::String("sha256".to_string()));
+        assert_eq!(object["index"], Value::Number(Number::from(3u64)));
+        assert_eq!(object["tree_size"], Value::Number(Number::from(5u64)));
+        assert_eq!(object.keys().next().unwrap(), "algorithm");
+    }
+
+    #[test]
+    fn root_round_trip() {
+        let root = tree().root;
+        let value = root_to_json(&root);
+        assert_eq!(root_from_json(&value), Ok(root.clone()));
+        assert_eq!(
+            scrach_json_parser::to_string(&value).unwrap(),
+            format!(r#"{{"algorithm":"sha256","root":"{}"}}"#, root.value())
+        );
+        assert_eq!(
+            root_from_json(&parse(&format!(r#"{{"root": "{}"}}"#, root.value()))),
+            Err(JsonError::MissingField("algorithm"))
+        );
+    }
+
+    #[test]
+    fn tree_head_round_trip() {
+        let tree = tree();
+        let head = TreeHead {
+            tree_size: 5,
+            root: tree.root.clone(),
+            timestamp: 1_640_995_200_000,
+        };
+        assert_eq!(TreeHead::from_json(&head.to_json()), Ok(head.clone()));
+
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let signed = SignedTreeHead {
+            signature: key.sign(&head.to_bytes()),
+            head,
+        };
+        let parsed = SignedTreeHead::from_json(&signed.to_json()).unwrap();
+        assert_eq!(parsed, signed);
+        assert!(parsed.verify(&key.verifying_key()));
+    }
+
+    #[test]
+    fn tree_round_trip() {
+        let tree = tree();
+        let parsed = Tree::from_json(&tree.to_json()).unwrap();
+        assert_eq!(parsed.root, tree.root);
+        assert_eq!(parsed.get_proof(2), tree.get_proof(2));
+
+        let mut kary = Tree::new(vec!["a", "b", "c", "d", "e"]).with_arity(4);
+        kary.build_tree();
+        let parsed = Tree::from_json(&kary.to_json()).unwrap();
+        assert_eq!(parsed.arity(), 4);
+        assert_eq!(parsed.root, kary.root);
+
+        let empty = Tree::new(vec![]);
+        assert_eq!(Tree::from_json(&empty.to_json()).unwrap().root, empty.root);
+        empty.root.to_bytes();
+    }
+
+    #[test]
+    fn malformed_documents() {
+        let hash = gen_hash("a".to_string());
+        let cases = [
+            ("[]", JsonError::NotAnObject("proof")),
+            (
+                r#"{"tree_size": 1, "index": 0, "path": null}"#,
+                JsonError::MissingField("algorithm"),
+            ),
+            (
+                r#"{"algorithm": "md5", "tree_size": 1, "index": 0, "path": null}"#,
+                JsonError::UnsupportedAlgorithm("md5".to_string()),
+            ),
+            (
+                r#"{"algorithm": "sha256", "tree_size": "1", "index": 0, "path": null}"#,
+                JsonError::InvalidType {
+                    field: "tree_size",
+                    expected: "a non-negative integer",
+                },
+            ),
+            (
+                r#"{"algorithm": "sha256", "tree_size": 2, "index": 0, "path": {}}"#,
+                JsonError::InvalidType {
+                    field: "path",
+                    expected: "an array",
+                },
+            ),
+            (
+                r#"{"algorithm": "sha256", "tree_size": 2, "index": 0,
+                    "path": [{"hash": "00", "position": "right"}]}"#,
+                JsonError::InvalidHash("00".to_string()),
+            ),
+        ];
+        for (json, error) in cases {
+            assert_eq!(MerkleProof::from_json(&parse(json)), Err(error), "{}", json);
+        }
+        let json = format!(
+            r#"{{"algorithm": "sha256", "tree_size": 2, "index": 0,
+                "path": [{{"hash": "{}", "position": "up"}}]}}"#,
+            hash.value()
+        );
+        assert_eq!(
+            MerkleProof::from_json(&parse(&json)),
+            Err(JsonError::InvalidPosition("up".to_string()))
+        );
+
+        let json = format!(
+            r#"{{"algorithm": "sha256", "arity": 2, "leaves": ["{}"], "root": "{}"}}"#,
+            hash.value(),
+            gen_hash("b".to_string()).value()
+        );
+        assert_eq!(
+            Tree::from_json(&parse(&json)).err(),
+            Some(JsonError::RootMismatch)
+        );
+    }
+}
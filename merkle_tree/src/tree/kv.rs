@@ -0,0 +1,227 @@
+use crate::proof::MerkleProof;
+use crate::{empty_root, gen_hash, Hash, Tree};
+use std::collections::BTreeMap;
+
+/// Tree over key-value pairs sorted by key.
+#[derive(Debug)]
+pub struct KvTree {
+    entries: Vec<(String, String)>,
+    tree: Tree,
+}
+
+/// A leaf of a `KvTree` with its inclusion proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryProof {
+    pub key: String,
+    pub value: String,
+    pub proof: MerkleProof,
+}
+
+/// Entries of a key range `[start, end)` with the leaves just outside of the
+/// range. As the leaves are sorted and their indices contiguous, no entry of
+/// the range can be left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeProof {
+    pub left: Option<EntryProof>,
+    pub entries: Vec<EntryProof>,
+    pub right: Option<EntryProof>,
+}
+
+/// Generate the hash of a key-value leaf. The key is length prefixed so that
+/// the boundary between key and value is unambiguous.
+pub fn entry_hash(key: &str, value: &str) -> Hash {
+    gen_hash(format!("{}:{}{}", key.len(), key, value))
+}
+
+impl KvTree {
+    pub fn new(entries: BTreeMap<String, String>) -> KvTree {
+        let entries: Vec<(String, String)> = entries.into_iter().collect();
+        let mut tree = Tree::from_hashes(
+            entries
+                .iter()
+                .map(|(key, value)| entry_hash(key, value))
+                .collect(),
+        );
+        tree.build_tree();
+        KvTree { entries, tree }
+    }
+
+    pub fn root(&self) -> &Hash {
+        &self.tree.root
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .binary_search_by(|(k, _)| k.as_str().cmp(key))
+            .ok()
+            .map(|i| self.entries[i].1.as_str())
+    }
+
+    fn entry_proof(&self, index: usize) -> Option<EntryProof> {
+        let (key, value) = self.entries.get(index)?;
+        Some(EntryProof {
+            key: key.clone(),
+            value: value.clone(),
+            proof: self.tree.get_proof(index)?,
+        })
+    }
+
+    /// Return the entries whose keys are in `[start, end)` with a proof that
+    /// none was left out.
+    pub fn range(&self, start: &str, end: &str) -> RangeProof {
+        let lo = self
+            .entries
+            .partition_point(|(key, _)| key.as_str() < start);
+        let hi = lo.max(self.entries.partition_point(|(key, _)| key.as_str() < end));
+        RangeProof {
+            left: lo.checked_sub(1).and_then(|i| self.entry_proof(i)),
+            entries: (lo..hi).filter_map(|i| self.entry_proof(i)).collect(),
+            right: self.entry_proof(hi),
+        }
+    }
+
+    /// Return a proof that `key` is not in the tree, made of its neighboring
+    /// leaves. `None` when the key is present.
+    pub fn absence(&self, key: &str) -> Option<RangeProof> {
+        let proof = self.range(key, &successor(key));
+        if proof.entries.is_empty() {
+            Some(proof)
+        } else {
+            None
+        }
+    }
+}
+
+/// The smallest string greater than `key`.
+fn successor(key: &str) -> String {
+    format!("{}\0", key)
+}
+
+impl RangeProof {
+    /// Check that `entries` are all the entries with keys in `[start, end)` of
+    /// the tree committed by `root`. A proof without any leaf only holds for
+    /// the empty tree.
+    pub fn verify(&self, start: &str, end: &str, root: &Hash) -> bool {
+        let proofs: Vec<&EntryProof> = self
+            .left
+            .iter()
+            .chain(&self.entries)
+            .chain(&self.right)
+            .collect();
+        let (first, last) = match (proofs.first(), proofs.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return *root == empty_root(),
+        };
+        let tree_size = first.proof.tree_size;
+        let included = proofs.iter().all(|p| {
+            p.proof.tree_size == tree_size && p.proof.verify(&entry_hash(&p.key, &p.value), root)
+        });
+        let contiguous = proofs
+            .windows(2)
+            .all(|pair| pair[1].proof.index == pair[0].proof.index + 1);
+        let bounded = (self.left.is_some() || first.proof.index == 0)
+            && (self.right.is_some() || last.proof.index + 1 == tree_size);
+        let left_outside = self.left.iter().all(|p| p.key.as_str() < start);
+        let right_outside = self.right.iter().all(|p| p.key.as_str() >= end);
+        let inside = self
+            .entries
+            .iter()
+            .all(|p| start <= p.key.as_str() && p.key.as_str() < end);
+        let sorted = proofs.windows(2).all(|pair| pair[0].key < pair[1].key);
+        included && contiguous && bounded && left_outside && right_outside && inside && sorted
+    }
+
+    /// Check that `key` is absent from the tree committed by `root`.
+    pub fn verify_absence(&self, key: &str, root: &Hash) -> bool {
+        self.entries.is_empty() && self.verify(key, &successor(key), root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kv_tree() -> KvTree {
+        let entries = [
+            "apple", "banana", "cherry", "grape", "lemon", "mango", "peach",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, key)| (key.to_string(), i.to_string()))
+        .collect();
+        KvTree::new(entries)
+    }
+
+    fn keys(proof: &RangeProof) -> Vec<&str> {
+        proof.entries.iter().map(|p| p.key.as_str()).collect()
+    }
+
+    #[test]
+    fn range() {
+        let tree = kv_tree();
+        let proof = tree.range("b", "h");
+        assert_eq!(keys(&proof), vec!["banana", "cherry", "grape"]);
+        assert!(proof.verify("b", "h", tree.root()));
+        assert!(!proof.verify("b", "m", tree.root()));
+        assert!(!proof.verify("c", "h", tree.root()));
+
+        let all = tree.range("", "z");
+        assert_eq!(all.entries.len(), 7);
+        assert!(all.left.is_none() && all.right.is_none());
+        assert!(all.verify("", "z", tree.root()));
+
+        let empty = tree.range("d", "f");
+        assert!(empty.entries.is_empty());
+        assert!(empty.verify("d", "f", tree.root()));
+    }
+
+    #[test]
+    fn omitted_entry() {
+        let tree = kv_tree();
+        let mut proof = tree.range("b", "h");
+        proof.entries.remove(1);
+        assert!(!proof.verify("b", "h", tree.root()));
+
+        let mut proof = tree.range("b", "h");
+        proof.right = None;
+        assert!(!proof.verify("b", "h", tree.root()));
+
+        let mut proof = tree.range("b", "h");
+        proof.entries[0].value = "forged".to_string();
+        assert!(!proof.verify("b", "h", tree.root()));
+    }
+
+    #[test]
+    fn absence() {
+        let tree = kv_tree();
+        assert_eq!(tree.get("cherry"), Some("2"));
+        assert!(tree.absence("cherry").is_none());
+
+        let proof = tree.absence("date").unwrap();
+        assert_eq!(proof.left.as_ref().unwrap().key, "cherry");
+        assert_eq!(proof.right.as_ref().unwrap().key, "grape");
+        assert!(proof.verify_absence("date", tree.root()));
+        // The same neighbors prove the absence of any key between them.
+        assert!(proof.verify_absence("fig", tree.root()));
+        assert!(!proof.verify_absence("cherry", tree.root()));
+
+        let first = tree.absence("aardvark").unwrap();
+        assert!(first.left.is_none());
+        assert!(first.verify_absence("aardvark", tree.root()));
+        let last = tree.absence("zucchini").unwrap();
+        assert!(last.right.is_none());
+        assert!(last.verify_absence("zucchini", tree.root()));
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree = KvTree::new(BTreeMap::new());
+        assert_eq!(tree.root(), &empty_root());
+        let proof = tree.absence("apple").unwrap();
+        assert!(proof.left.is_none() && proof.entries.is_empty() && proof.right.is_none());
+        assert!(proof.verify_absence("apple", tree.root()));
+        assert!(tree.range("", "z").verify("", "z", tree.root()));
+        // An empty proof says nothing about a tree with entries.
+        assert!(!proof.verify_absence("apple", kv_tree().root()));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::proof::{binary_proof, MerkleProof};
+use crate::verify::Positions;
+use crate::{build_levels, empty_root, gen_hash, hash_children, Hash};
+use std::collections::BTreeSet;
+
+/// Counters of the hashing work of a `LazyTree`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LazyStats {
+    pub updates: usize,
+    /// Parent hashes computed by flushes.
+    pub hashes_computed: usize,
+    /// Parent hashes recomputing the path after every update would have cost.
+    pub hashes_eager: usize,
+}
+
+impl LazyStats {
+    /// Number of hashes saved by deferring the recomputation.
+    pub fn hashes_avoided(&self) -> usize {
+        self.hashes_eager.saturating_sub(self.hashes_computed)
+    }
+}
+
+/// Binary tree whose leaves can be updated in batches. An update only marks
+/// the path of the leaf dirty and the dirty nodes are hashed once, the next
+/// time the root or a proof is requested.
+#[derive(Debug)]
+pub struct LazyTree {
+    levels: Vec<Vec<Hash>>,
+    /// Indices of the dirty nodes of every level above the leaves.
+    dirty: Vec<BTreeSet<usize>>,
+    stats: LazyStats,
+}
+
+impl LazyTree {
+    pub fn new(data: Vec<&str>) -> LazyTree {
+        LazyTree::from_hashes(
+            data.iter()
+                .map(|datum| gen_hash(datum.to_string()))
+                .collect(),
+        )
+    }
+
+    pub fn from_hashes(leaves: Vec<Hash>) -> LazyTree {
+        let levels = build_levels(leaves, 2, hash_children);
+        LazyTree {
+            dirty: vec![BTreeSet::new(); levels.len() - 1],
+            levels,
+            stats: LazyStats::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    pub fn stats(&self) -> LazyStats {
+        self.stats
+    }
+
+    pub fn update(&mut self, index: usize, datum: &str) {
+        self.update_hash(index, gen_hash(datum.to_string()));
+    }
+
+    /// Replace the hash of the leaf at `index` and mark its ancestors dirty.
+    pub fn update_hash(&mut self, index: usize, leaf: Hash) {
+        assert!(index < self.len(), "leaf index is out of the tree");
+        self.levels[0][index] = leaf;
+        self.stats.updates += 1;
+        self.stats.hashes_eager += Positions::new(self.len(), index).count();
+        let mut i = index;
+        for dirty in &mut self.dirty {
+            i /= 2;
+            if !dirty.insert(i) {
+                // The rest of the path is already dirty.
+                break;
+            }
+        }
+    }
+
+    /// Whether some nodes wait for their hash.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.iter().any(|dirty| !dirty.is_empty())
+    }
+
+    /// Hash the dirty nodes from the bottom up.
+    fn flush(&mut self) {
+        for level in 1..self.levels.len() {
+            for i in std::mem::take(&mut self.dirty[level - 1]) {
+                let children = &self.levels[level - 1];
+                let end = children.len().min(2 * i + 2);
+                let hash = hash_children(&children[2 * i..end]);
+                if end - 2 * i > 1 {
+                    self.stats.hashes_computed += 1;
+                }
+                self.levels[level][i] = hash;
+            }
+        }
+    }
+
+    /// Root of the tree, the hash of no data when it has no leaves.
+    pub fn root(&mut self) -> Hash {
+        self.flush();
+        self.levels[self.levels.len() - 1]
+            .first()
+            .cloned()
+            .unwrap_or_else(empty_root)
+    }
+
+    pub fn get_proof(&mut self, index: usize) -> Option<MerkleProof> {
+        self.flush();
+        binary_proof(&self.levels, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::build;
+
+    fn data(size: usize, version: usize) -> Vec<String> {
+        (0..size).map(|i| format!("{}-{}", i, version)).collect()
+    }
+
+    fn eager_root(data: &[String]) -> Hash {
+        build(data, 2).root
+    }
+
+    #[test]
+    fn same_root_as_tree() {
+        for size in [1, 2, 5, 16, 33] {
+            let mut current = data(size, 0);
+            let mut tree = LazyTree::new(current.iter().map(String::as_str).collect());
+            assert_eq!(tree.root(), eager_root(&current));
+            for i in (0..size).step_by(3) {
+                current[i] = format!("{}-1", i);
+                tree.update(i, &current[i]);
+            }
+            assert!(size == 1 || tree.is_dirty());
+            assert_eq!(tree.root(), eager_root(&current));
+            assert!(!tree.is_dirty());
+
+            let proof = tree.get_proof(size - 1).unwrap();
+            assert!(proof.verify(&gen_hash(current[size - 1].clone()), &tree.root()));
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let mut tree = LazyTree::new(vec![]);
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), eager_root(&[]));
+        assert_eq!(tree.root(), empty_root());
+        assert!(tree.get_proof(0).is_none());
+    }
+
+    #[test]
+    fn batch_avoids_hashes() {
+        let mut tree = LazyTree::new(data(1024, 0).iter().map(String::as_str).collect());
+        for (i, datum) in data(1024, 1).iter().enumerate() {
+            tree.update(i, datum);
+        }
+        tree.root();
+        let stats = tree.stats();
+        assert_eq!(stats.updates, 1024);
+        // Every parent once instead of a whole path per update.
+        assert_eq!(stats.hashes_computed, 1023);
+        assert_eq!(stats.hashes_eager, 1024 * 10);
+        assert_eq!(stats.hashes_avoided(), 1024 * 10 - 1023);
+
+        // Reading the root again costs nothing.
+        tree.root();
+        assert_eq!(tree.stats().hashes_computed, 1023);
+    }
+}
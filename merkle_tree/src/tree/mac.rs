@@ -0,0 +1,241 @@
+use crate::proof::{binary_proof, MerkleProof};
+use crate::{build_levels, Hash};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+const BLOCK_SIZE: usize = 64;
+
+/// Prefixes of the MAC inputs of leaves, nodes and the root of an empty tree,
+/// so that the MAC of one can never be passed off as the MAC of another.
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+const EMPTY_TAG: u8 = 0x02;
+
+/// Secret key of a keyed tree.
+#[derive(Clone)]
+pub struct MacKey {
+    key: Vec<u8>,
+}
+
+impl MacKey {
+    pub fn new(key: &[u8]) -> MacKey {
+        MacKey { key: key.to_vec() }
+    }
+}
+
+impl fmt::Debug for MacKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MacKey(..)")
+    }
+}
+
+/// HMAC-SHA256 as in RFC 2104.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(data);
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(&outer.finalize());
+    ret
+}
+
+fn mac_tagged(key: &MacKey, tag: u8, data: &[u8]) -> Hash {
+    let mut input = Vec::with_capacity(data.len() + 1);
+    input.push(tag);
+    input.extend_from_slice(data);
+    Hash::from_bytes(&hmac_sha256(&key.key, &input))
+}
+
+/// Keyed hash of a leaf, the counterpart of `gen_hash`.
+pub fn mac_leaf(key: &MacKey, datum: &str) -> Hash {
+    mac_tagged(key, LEAF_TAG, datum.as_bytes())
+}
+
+/// Keyed hash of a parent node, the counterpart of `hash_pair`.
+fn mac_pair(key: &MacKey, left: &Hash, right: &Hash) -> Hash {
+    let data = format!("{}{}", left.value, right.value);
+    mac_tagged(key, NODE_TAG, data.as_bytes())
+}
+
+/// Keyed root of a tree without leaves, which no one can compute without the
+/// key either.
+fn mac_empty(key: &MacKey) -> Hash {
+    mac_tagged(key, EMPTY_TAG, b"")
+}
+
+/// Compare two hashes in constant time.
+fn hash_eq(a: &Hash, b: &Hash) -> bool {
+    a.value.len() == b.value.len()
+        && a.value
+            .bytes()
+            .zip(b.value.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// Binary tree whose leaves and nodes are all keyed with HMAC-SHA256. Without
+/// the key nobody can compute a root, so the root is only evidence of tampering
+/// for the holders of the key.
+#[derive(Debug)]
+pub struct MacTree {
+    pub root: Hash,
+    data: Vec<String>,
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MacTree {
+    pub fn new(key: &MacKey, data: Vec<&str>) -> MacTree {
+        let leaves = data.iter().map(|datum| mac_leaf(key, datum)).collect();
+        let levels = if data.is_empty() {
+            vec![]
+        } else {
+            build_levels(leaves, 2, |children: &[Hash]| {
+                if children.len() == 1 {
+                    children[0].clone()
+                } else {
+                    mac_pair(key, &children[0], &children[1])
+                }
+            })
+        };
+        MacTree {
+            root: levels
+                .last()
+                .map_or_else(|| mac_empty(key), |level| level[0].clone()),
+            data: data.iter().map(|datum| datum.to_string()).collect(),
+            levels,
+        }
+    }
+
+    /// Rebuild the tree with the same data under `key`.
+    pub fn rekey(&self, key: &MacKey) -> MacTree {
+        MacTree::new(key, self.data.iter().map(String::as_str).collect())
+    }
+
+    pub fn get_proof(&self, index: usize) -> Option<MerkleProof> {
+        binary_proof(&self.levels, index)
+    }
+}
+
+impl MerkleProof {
+    /// Check with `key` that `datum` is at `index` of the keyed tree committed
+    /// by `root`.
+    pub fn verify_mac(&self, key: &MacKey, datum: &str, root: &Hash) -> bool {
+        let computed = self.root_with(&mac_leaf(key, datum), |left, right| {
+            mac_pair(key, left, right)
+        });
+        self.is_well_formed() && hash_eq(&computed, root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc4231() {
+        // Test case 1
+        assert_eq!(
+            hex::encode(hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        // Test case 2
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Test case 6, key longer than a block
+        assert_eq!(
+            hex::encode(hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn keyed_proof() {
+        let key = MacKey::new(b"secret");
+        let data = vec!["log 1", "log 2", "log 3", "log 4", "log 5"];
+        let tree = MacTree::new(&key, data.clone());
+        for (i, datum) in data.iter().enumerate() {
+            let proof = tree.get_proof(i).unwrap();
+            assert!(proof.verify_mac(&key, datum, &tree.root));
+            assert!(!proof.verify_mac(&MacKey::new(b"guess"), datum, &tree.root));
+            assert!(!proof.verify_mac(&key, "forged", &tree.root));
+        }
+
+        let mut unkeyed = crate::Tree::new(data);
+        unkeyed.build_tree();
+        assert_ne!(tree.root, unkeyed.root);
+    }
+
+    #[test]
+    fn nodes_are_not_leaves() {
+        let key = MacKey::new(b"secret");
+        let tree = MacTree::new(&key, vec!["log 1", "log 2", "log 3", "log 4"]);
+        let proofs: Vec<MerkleProof> = (0..4).map(|i| tree.get_proof(i).unwrap()).collect();
+        // Without the key, the sibling hashes of the proofs give every leaf and
+        // node of the tree. Collapse each pair of leaves into one entry.
+        let leaves: Vec<&str> = [1, 0, 3, 2]
+            .iter()
+            .map(|&i| proofs[i].path[0].hash.value())
+            .collect();
+        let forged = [
+            leaves[0].to_string() + leaves[1],
+            leaves[2].to_string() + leaves[3],
+        ];
+        let collapsed = MacTree::new(&key, forged.iter().map(String::as_str).collect());
+        assert_ne!(collapsed.root, tree.root);
+
+        let proof = MerkleProof {
+            tree_size: 2,
+            index: 0,
+            path: vec![proofs[0].path[1].clone()],
+        };
+        assert!(!proof.verify_mac(&key, &forged[0], &tree.root));
+    }
+
+    #[test]
+    fn empty_root_is_keyed() {
+        let key = MacKey::new(b"secret");
+        let empty = MacTree::new(&key, vec![]);
+        assert_eq!(empty.root, MacTree::new(&key, vec![]).root);
+        assert_ne!(
+            empty.root,
+            MacTree::new(&MacKey::new(b"guess"), vec![]).root
+        );
+        assert_ne!(empty.root, crate::empty_root());
+        assert_ne!(empty.root, mac_leaf(&key, ""));
+    }
+
+    #[test]
+    fn key_rotation() {
+        let old_key = MacKey::new(b"2021");
+        let new_key = MacKey::new(b"2022");
+        let tree = MacTree::new(&old_key, vec!["a", "b", "c"]);
+        let rotated = tree.rekey(&new_key);
+        assert_ne!(tree.root, rotated.root);
+        assert_eq!(
+            rotated.root,
+            MacTree::new(&new_key, vec!["a", "b", "c"]).root
+        );
+
+        let proof = rotated.get_proof(2).unwrap();
+        assert!(proof.verify_mac(&new_key, "c", &rotated.root));
+        assert!(!proof.verify_mac(&old_key, "c", &rotated.root));
+        assert!(!tree
+            .get_proof(2)
+            .unwrap()
+            .verify_mac(&old_key, "c", &rotated.root));
+    }
+}
@@ -0,0 +1,398 @@
+use crate::verify::{EncodedProof, Positions};
+use crate::{hash_children, hash_pair, Hash};
+
+pub use crate::verify::{DecodeError, Position, PROOF_VERSION};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofNode {
+    pub hash: Hash,
+    pub position: Position,
+}
+
+/// Inclusion proof of the leaf at `index` in a tree of `tree_size` leaves.
+/// `path` lists the siblings from the leaf up to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub tree_size: usize,
+    pub index: usize,
+    pub path: Vec<ProofNode>,
+}
+
+/// Inclusion proof for a tree of any arity. `levels` holds, for every level
+/// from the leaves up, the other children of the node on the path in order.
+/// A promoted node has no siblings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KaryProof {
+    pub arity: usize,
+    pub tree_size: usize,
+    pub index: usize,
+    pub levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleProof {
+    /// Recompute the root from the hash of the proven leaf.
+    pub fn root(&self, leaf: &Hash) -> Hash {
+        self.root_with(leaf, hash_pair)
+    }
+
+    /// Recompute the root with `pair` hashing a left and a right child.
+    pub(crate) fn root_with(&self, leaf: &Hash, pair: impl Fn(&Hash, &Hash) -> Hash) -> Hash {
+        let mut ret = leaf.clone();
+        for node in &self.path {
+            ret = match node.position {
+                Position::Left => pair(&node.hash, &ret),
+                Position::Right => pair(&ret, &node.hash),
+            };
+        }
+        ret
+    }
+
+    /// Whether the sides of the path match the index and the tree size.
+    pub(crate) fn is_well_formed(&self) -> bool {
+        self.index < self.tree_size
+            && self
+                .path
+                .iter()
+                .map(|node| node.position)
+                .eq(positions(self.tree_size, self.index))
+    }
+
+    /// Check that `leaf` is at `index` of the tree committed by `root`.
+    pub fn verify(&self, leaf: &Hash, root: &Hash) -> bool {
+        self.is_well_formed() && &self.root(leaf) == root
+    }
+
+    /// Encode the proof as
+    /// `version | varint(tree_size) | varint(index) | siblings | bitmap`.
+    /// Bit `i` of the bitmap (LSB first) is set when the sibling `i` is on the left.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![PROOF_VERSION];
+        write_varint(&mut bytes, self.tree_size as u64);
+        write_varint(&mut bytes, self.index as u64);
+        for node in &self.path {
+            bytes.extend_from_slice(&node.hash.to_bytes());
+        }
+        let mut bitmap = vec![0u8; self.path.len().div_ceil(8)];
+        for (i, node) in self.path.iter().enumerate() {
+            if node.position == Position::Left {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes.extend_from_slice(&bitmap);
+        bytes
+    }
+
+    /// Decode a proof produced by `encode`. Anything but the exact canonical
+    /// encoding is rejected.
+    pub fn decode(bytes: &[u8]) -> Result<MerkleProof, DecodeError> {
+        let proof = EncodedProof::parse(bytes)?;
+        Ok(MerkleProof {
+            tree_size: proof.tree_size,
+            index: proof.index,
+            path: proof
+                .siblings()
+                .map(|(position, hash)| ProofNode {
+                    hash: Hash::from_bytes(&hash),
+                    position,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl KaryProof {
+    /// Recompute the root from the hash of the proven leaf. Return `None` when
+    /// the shape of the proof does not match the arity, size and index.
+    pub fn root(&self, leaf: &Hash) -> Option<Hash> {
+        if self.arity < 2 {
+            return None;
+        }
+        fold_wide_path(
+            self.arity,
+            self.tree_size,
+            self.index,
+            &self.levels,
+            leaf,
+            |i, width, child, siblings| {
+                if siblings.len() + 1 != width {
+                    return None;
+                }
+                let mut children = siblings.clone();
+                children.insert(i, child.clone());
+                Some(hash_children(&children))
+            },
+        )
+    }
+
+    /// Check that `leaf` is at `index` of the tree committed by `root`.
+    pub fn verify(&self, leaf: &Hash, root: &Hash) -> bool {
+        self.root(leaf).as_ref() == Some(root)
+    }
+}
+
+/// Return the inclusion proof of the leaf at `index` from the hashed levels of
+/// a binary tree.
+pub(crate) fn binary_proof(levels: &[Vec<Hash>], index: usize) -> Option<MerkleProof> {
+    let tree_size = levels.first()?.len();
+    if index >= tree_size {
+        return None;
+    }
+    let mut path = vec![];
+    let mut i = index;
+    for level in &levels[..levels.len() - 1] {
+        if i % 2 == 1 {
+            path.push(ProofNode {
+                hash: level[i - 1].clone(),
+                position: Position::Left,
+            });
+        } else if i + 1 < level.len() {
+            path.push(ProofNode {
+                hash: level[i + 1].clone(),
+                position: Position::Right,
+            });
+        }
+        i /= 2;
+    }
+    Some(MerkleProof {
+        tree_size,
+        index,
+        path,
+    })
+}
+
+/// Return the inclusion proof of the leaf at `index` from the hashed levels of
+/// a tree of the given arity.
+pub(crate) fn kary_proof(levels: &[Vec<Hash>], arity: usize, index: usize) -> Option<KaryProof> {
+    let tree_size = levels.first()?.len();
+    let levels = wide_children(levels, arity, index)?
+        .into_iter()
+        .map(|(i, children)| {
+            let mut siblings = children.to_vec();
+            siblings.remove(i);
+            siblings
+        })
+        .collect();
+    Some(KaryProof {
+        arity,
+        tree_size,
+        index,
+        levels,
+    })
+}
+
+/// Return, for every level below the root, the children of the node on the
+/// path of `index` and the position of the path among them.
+pub(crate) fn wide_children(
+    levels: &[Vec<Hash>],
+    arity: usize,
+    index: usize,
+) -> Option<Vec<(usize, &[Hash])>> {
+    if index >= levels.first()?.len() {
+        return None;
+    }
+    let mut ret = vec![];
+    let mut i = index;
+    for level in &levels[..levels.len() - 1] {
+        let start = i - i % arity;
+        ret.push((i % arity, &level[start..level.len().min(start + arity)]));
+        i /= arity;
+    }
+    Some(ret)
+}
+
+/// Recompute a root from `leaf` up the path of `index`, where `node` hashes a
+/// node from the position of the path among its children, their number, the
+/// hash on the path and the step of the proof for that level. Return `None`
+/// when the steps do not match the shape of the tree or `node` rejects one.
+pub(crate) fn fold_wide_path<T>(
+    arity: usize,
+    tree_size: usize,
+    index: usize,
+    steps: &[T],
+    leaf: &Hash,
+    mut node: impl FnMut(usize, usize, &Hash, &T) -> Option<Hash>,
+) -> Option<Hash> {
+    if index >= tree_size {
+        return None;
+    }
+    let shape = wide_path(arity, tree_size, index);
+    if shape.len() != steps.len() {
+        return None;
+    }
+    let mut ret = leaf.clone();
+    for ((i, width), step) in shape.into_iter().zip(steps) {
+        ret = node(i, width, &ret, step)?;
+    }
+    Some(ret)
+}
+
+/// Return, for every node on the path of `index` in a tree of `tree_size`
+/// leaves and the given arity, the position of the path among the children of
+/// the node and the number of these children. A promoted node has one child.
+pub(crate) fn wide_path(arity: usize, tree_size: usize, index: usize) -> Vec<(usize, usize)> {
+    let mut ret = vec![];
+    let mut len = tree_size;
+    let mut i = index;
+    while len > 1 {
+        let start = i - i % arity;
+        ret.push((i % arity, arity.min(len - start)));
+        i /= arity;
+        len = len.div_ceil(arity);
+    }
+    ret
+}
+
+/// Return the side of every sibling on the path of `index` in a tree of
+/// `tree_size` leaves. Promoted nodes have no sibling.
+fn positions(tree_size: usize, index: usize) -> Vec<Position> {
+    Positions::new(tree_size, index).collect()
+}
+
+/// Write `value` as an unsigned LEB128 varint.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen_hash;
+    use crate::testing::{build, data};
+    use crate::verify::read_varint;
+
+    #[test]
+    fn round_trip() {
+        for size in 1..20 {
+            let tree = build(&data(size), 2);
+            for index in 0..size {
+                let proof = tree.get_proof(index).unwrap();
+                let decoded = MerkleProof::decode(&proof.encode()).unwrap();
+                assert_eq!(decoded, proof);
+                assert!(decoded.verify(&gen_hash(index.to_string()), &tree.root));
+            }
+        }
+    }
+
+    #[test]
+    fn kary_proofs() {
+        for arity in [2, 4, 8, 16] {
+            for size in [1, 2, 15, 16, 17, 40] {
+                let data = data(size);
+                let tree = build(&data, arity);
+                for index in 0..size {
+                    let proof = tree.get_kary_proof(index).unwrap();
+                    assert!(proof.verify(&gen_hash(index.to_string()), &tree.root));
+                    assert!(!proof.verify(&gen_hash("x".to_string()), &tree.root));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn kary_proof_shape() {
+        let tree = build(&data(20), 4);
+        let proof = tree.get_kary_proof(17).unwrap();
+        // 20 leaves -> 5 nodes -> 2 nodes -> root
+        assert_eq!(
+            proof.levels.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![3, 0, 1]
+        );
+        let leaf = gen_hash("17".to_string());
+
+        let mut wrong_arity = proof.clone();
+        wrong_arity.arity = 2;
+        assert_eq!(wrong_arity.root(&leaf), None);
+
+        let mut missing = proof.clone();
+        missing.levels[0].pop();
+        assert_eq!(missing.root(&leaf), None);
+
+        let mut wrong_index = proof;
+        wrong_index.index = 16;
+        assert!(!wrong_index.verify(&leaf, &tree.root));
+    }
+
+    #[test]
+    fn encoded_size() {
+        // version + 2 varints + 3 siblings + 1 byte bitmap
+        let proof = build(&data(8), 2).get_proof(5).unwrap();
+        assert_eq!(proof.encode().len(), 1 + 1 + 1 + 3 * 32 + 1);
+    }
+
+    #[test]
+    fn varint() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut bytes = vec![];
+            write_varint(&mut bytes, value);
+            let mut slice = bytes.as_slice();
+            assert_eq!(read_varint(&mut slice), Ok(value));
+            assert!(slice.is_empty());
+        }
+        assert_eq!(
+            read_varint(&mut [0x80, 0x00].as_slice()),
+            Err(DecodeError::NonCanonicalVarint)
+        );
+        let mut overflow = vec![0xff; 9];
+        overflow.push(0x02);
+        assert_eq!(
+            read_varint(&mut overflow.as_slice()),
+            Err(DecodeError::VarintOverflow)
+        );
+    }
+
+    #[test]
+    fn reject_malformed() {
+        let bytes = build(&data(5), 2).get_proof(2).unwrap().encode();
+
+        assert_eq!(MerkleProof::decode(&[]), Err(DecodeError::UnexpectedEof));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = 2;
+        assert_eq!(
+            MerkleProof::decode(&wrong_version),
+            Err(DecodeError::UnsupportedVersion(2))
+        );
+
+        assert_eq!(
+            MerkleProof::decode(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEof)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            MerkleProof::decode(&trailing),
+            Err(DecodeError::TrailingBytes)
+        );
+
+        let mut out_of_range = bytes.clone();
+        out_of_range[2] = 5;
+        assert_eq!(
+            MerkleProof::decode(&out_of_range),
+            Err(DecodeError::IndexOutOfRange)
+        );
+
+        let last = bytes.len() - 1;
+        let mut flipped = bytes.clone();
+        flipped[last] ^= 0b1;
+        assert_eq!(
+            MerkleProof::decode(&flipped),
+            Err(DecodeError::DirectionMismatch)
+        );
+
+        let mut padding = bytes;
+        padding[last] |= 0x80;
+        assert_eq!(
+            MerkleProof::decode(&padding),
+            Err(DecodeError::NonZeroPadding)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{Hash, Tree};
+use std::fmt::Write;
+
+/// Number of hex characters shown for a hash.
+const SHORT_HASH_LEN: usize = 8;
+
+fn short(hash: &Hash) -> &str {
+    &hash.value[..SHORT_HASH_LEN.min(hash.value.len())]
+}
+
+impl Tree {
+    /// Render the tree level by level from the root down to the leaves.
+    /// `build_tree` must be called before.
+    ///
+    /// ```text
+    /// 2: 58c89d70
+    /// 1: 62af5c3c d3a0f1c7
+    /// 0: ca978112 3e23e816 2e7d2c03 18ac3e73
+    /// ```
+    pub fn render_ascii(&self) -> String {
+        let mut ret = String::new();
+        for (depth, level) in self.levels.iter().enumerate().rev() {
+            let hashes: Vec<&str> = level.iter().map(short).collect();
+            writeln!(ret, "{}: {}", depth, hashes.join(" ")).unwrap();
+        }
+        ret
+    }
+
+    /// Export the tree as a Graphviz DOT graph. When `highlight` is given, the
+    /// path from that leaf to the root and the siblings of its proof are colored.
+    /// `build_tree` must be called before.
+    pub fn to_dot(&self, highlight: Option<usize>) -> String {
+        let mut path = vec![];
+        let mut siblings = vec![];
+        if let Some(mut i) = highlight.filter(|i| *i < self.levels.first().map_or(0, Vec::len)) {
+            for (depth, level) in self.levels.iter().enumerate() {
+                path.push((depth, i));
+                let start = i - i % self.arity;
+                for sibling in start..(start + self.arity).min(level.len()) {
+                    if sibling != i {
+                        siblings.push((depth, sibling));
+                    }
+                }
+                i /= self.arity;
+            }
+        }
+
+        let mut ret = String::new();
+        ret.push_str("digraph merkle {\n");
+        ret.push_str("  node [shape=box, fontname=monospace];\n");
+        for (depth, level) in self.levels.iter().enumerate().rev() {
+            for (i, hash) in level.iter().enumerate() {
+                let style = if path.contains(&(depth, i)) {
+                    ", style=filled, fillcolor=lightcoral"
+                } else if siblings.contains(&(depth, i)) {
+                    ", style=filled, fillcolor=lightblue"
+                } else {
+                    ""
+                };
+                writeln!(
+                    ret,
+                    "  n{}_{} [label=\"{}\"{}];",
+                    depth,
+                    i,
+                    short(hash),
+                    style
+                )
+                .unwrap();
+            }
+        }
+        for depth in 1..self.levels.len() {
+            let children = &self.levels[depth - 1];
+            for i in 0..self.levels[depth].len() {
+                let start = i * self.arity;
+                let end = (start + self.arity).min(children.len());
+                for child in start..end {
+                    // A promoted node is the same node one level up.
+                    let style = if start + 1 == end {
+                        " [style=dashed]"
+                    } else {
+                        ""
+                    };
+                    writeln!(
+                        ret,
+                        "  n{}_{} -> n{}_{}{};",
+                        depth,
+                        i,
+                        depth - 1,
+                        child,
+                        style
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        ret.push_str("}\n");
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    #[test]
+    fn ascii() {
+        let mut tree = Tree::new(vec!["a", "b", "c"]);
+        tree.build_tree();
+        let ascii = tree.render_ascii();
+        let lines: Vec<&str> = ascii.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], format!("2: {}", &tree.root.value()[..8]));
+        assert_eq!(lines[2], "0: ca978112 3e23e816 2e7d2c03");
+    }
+
+    #[test]
+    fn dot_highlight() {
+        let mut tree = Tree::new(vec!["a", "b", "c", "d"]);
+        tree.build_tree();
+        let dot = tree.to_dot(Some(1));
+        assert!(dot.starts_with("digraph merkle {"));
+        assert!(dot.contains("n0_1 [label=\"3e23e816\", style=filled, fillcolor=lightcoral];"));
+        assert!(dot.contains("n0_0 [label=\"ca978112\", style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("n1_1 [label=\"d3a0f1c7\", style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("n0_2 [label=\"2e7d2c03\"];"));
+        assert!(dot.contains("n2_0 -> n1_1;"));
+        assert_eq!(dot.matches(" -> ").count(), 6);
+    }
+
+    #[test]
+    fn dot_kary() {
+        let mut tree = Tree::new(vec!["a", "b", "c", "d", "e"]).with_arity(4);
+        tree.build_tree();
+        let dot = tree.to_dot(Some(4));
+        assert!(dot.contains("n1_1 -> n0_4 [style=dashed];"));
+        assert!(dot.contains("n1_0 [label=\"51bcf073\", style=filled, fillcolor=lightblue];"));
+        assert_eq!(dot.matches(" -> ").count(), 7);
+    }
+}
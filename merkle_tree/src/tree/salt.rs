@@ -0,0 +1,110 @@
+use crate::proof::MerkleProof;
+use crate::{gen_hash, Hash, Tree};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+pub type Salt = [u8; 32];
+
+/// Inclusion proof of a salted leaf. The salt of the leaf is revealed with the
+/// proof, the salts of the other leaves stay hidden behind their hashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaltedProof {
+    pub salt: Salt,
+    pub proof: MerkleProof,
+}
+
+impl SaltedProof {
+    /// Check that `datum` is at `index` of the tree committed by `root`.
+    pub fn verify(&self, datum: &str, root: &Hash) -> bool {
+        self.proof.verify(&salted_hash(datum, &self.salt), root)
+    }
+}
+
+/// Generate a hash of the input string prefixed with the salt.
+pub fn salted_hash(datum: &str, salt: &Salt) -> Hash {
+    gen_hash(format!("{}{}", hex::encode(salt), datum))
+}
+
+/// Generate `count` salts from the OS random number generator.
+pub fn random_salts(count: usize) -> Vec<Salt> {
+    let mut rng = rand::thread_rng();
+    (0..count).map(|_| rng.gen()).collect()
+}
+
+/// Generate `count` salts deterministically from `seed`. Only for tests, the
+/// salts are as guessable as the seed.
+pub fn seeded_salts(seed: u64, count: usize) -> Vec<Salt> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let mut salt = [0u8; 32];
+            rng.fill_bytes(&mut salt);
+            salt
+        })
+        .collect()
+}
+
+impl Tree {
+    /// Create a tree whose leaves are hashed with one salt per datum.
+    pub fn new_salted(data: Vec<&str>, salts: Vec<Salt>) -> Tree {
+        assert_eq!(data.len(), salts.len(), "one salt is needed per datum");
+        let mut tree = Tree::new(data.clone());
+        for (node, (datum, salt)) in tree.layers.iter_mut().zip(data.iter().zip(&salts)) {
+            node.hash = salted_hash(datum, salt);
+        }
+        tree.salts = salts;
+        tree
+    }
+
+    pub fn salt(&self, index: usize) -> Option<&Salt> {
+        self.salts.get(index)
+    }
+
+    /// Return the proof of the salted leaf at `index` with its salt.
+    /// `build_tree` must be called before.
+    pub fn get_salted_proof(&self, index: usize) -> Option<SaltedProof> {
+        Some(SaltedProof {
+            salt: *self.salt(index)?,
+            proof: self.get_proof(index)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_salts_are_deterministic() {
+        assert_eq!(seeded_salts(42, 3), seeded_salts(42, 3));
+        assert_ne!(seeded_salts(42, 3), seeded_salts(43, 3));
+        let salts = seeded_salts(42, 3);
+        assert_ne!(salts[0], salts[1]);
+        assert_ne!(random_salts(1), random_salts(1));
+    }
+
+    #[test]
+    fn salted_proof() {
+        let data = vec!["age=32", "is_married=false", "name=Yuhei"];
+        let mut tree = Tree::new_salted(data.clone(), seeded_salts(7, data.len()));
+        tree.build_tree();
+
+        let mut unsalted = Tree::new(data.clone());
+        unsalted.build_tree();
+        assert_ne!(tree.root, unsalted.root);
+
+        let proof = tree.get_salted_proof(1).unwrap();
+        assert!(proof.verify("is_married=false", &tree.root));
+        assert!(!proof.verify("is_married=true", &tree.root));
+        // The bare proof does not open the leaf without its salt.
+        assert!(!proof
+            .proof
+            .verify(&gen_hash("is_married=false".to_string()), &tree.root));
+
+        let mut wrong_salt = proof;
+        wrong_salt.salt = seeded_salts(8, 1)[0];
+        assert!(!wrong_salt.verify("is_married=false", &tree.root));
+
+        assert!(unsalted.get_salted_proof(1).is_none());
+    }
+}
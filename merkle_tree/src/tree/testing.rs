@@ -0,0 +1,50 @@
+//! Fixtures shared by the tests of this crate and of the crates built on it.
+
+use crate::Tree;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Empty directory under the system temporary directory, removed with its
+/// contents when dropped.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// `name` must be unique among the tests of a crate, as they run in
+    /// parallel.
+    pub fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("merkle_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// The data `"0"`, `"1"`, ... of `size` leaves.
+pub fn data(size: usize) -> Vec<String> {
+    (0..size).map(|i| i.to_string()).collect()
+}
+
+/// Built tree of `arity` children per node over `data`.
+pub fn build(data: &[String], arity: usize) -> Tree {
+    let mut tree = Tree::new(data.iter().map(String::as_str).collect()).with_arity(arity);
+    tree.build_tree();
+    tree
+}
@@ -0,0 +1,690 @@
+use crate::proof::{binary_proof, MerkleProof};
+use crate::{build_levels, empty_root, Hash};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const ENTRIES_FILE: &str = "entries";
+const HEADS_FILE: &str = "heads";
+/// Domain separation of the signed tree head message.
+const TREE_HEAD_PREFIX: &[u8] = b"merkle-tree-head:v1";
+/// Size of a persisted signed tree head: size, root, timestamp and signature.
+const SIGNED_HEAD_LEN: usize = 8 + 32 + 8 + 64;
+
+/// Prefixes of leaf and node hashes (RFC 9162, section 2.1.1), so that no
+/// entry can be passed off as an inner node.
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeHead {
+    pub tree_size: usize,
+    pub root: Hash,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl TreeHead {
+    /// Message signed by the log.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = TREE_HEAD_PREFIX.to_vec();
+        bytes.extend_from_slice(&(self.tree_size as u64).to_be_bytes());
+        bytes.extend_from_slice(&self.root.to_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTreeHead {
+    pub head: TreeHead,
+    pub signature: Signature,
+}
+
+impl SignedTreeHead {
+    fn to_bytes(&self) -> [u8; SIGNED_HEAD_LEN] {
+        let mut bytes = [0u8; SIGNED_HEAD_LEN];
+        bytes[..8].copy_from_slice(&(self.head.tree_size as u64).to_be_bytes());
+        bytes[8..40].copy_from_slice(&self.head.root.to_bytes());
+        bytes[40..48].copy_from_slice(&self.head.timestamp.to_be_bytes());
+        bytes[48..].copy_from_slice(&self.signature.to_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> SignedTreeHead {
+        let mut root = [0u8; 32];
+        root.copy_from_slice(&bytes[8..40]);
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&bytes[48..]);
+        SignedTreeHead {
+            head: TreeHead {
+                tree_size: u64::from_be_bytes(bytes[..8].try_into().unwrap()) as usize,
+                root: Hash::from_bytes(&root),
+                timestamp: u64::from_be_bytes(bytes[40..48].try_into().unwrap()),
+            },
+            signature: Signature::from_bytes(&signature),
+        }
+    }
+
+    pub fn verify(&self, key: &VerifyingKey) -> bool {
+        key.verify(&self.head.to_bytes(), &self.signature).is_ok()
+    }
+}
+
+/// Proof that the tree of `old_size` leaves is a prefix of the tree of
+/// `new_size` leaves, as in RFC 9162.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    pub old_size: usize,
+    pub new_size: usize,
+    pub path: Vec<Hash>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    BadSignature,
+    TreeShrunk,
+    Inconsistent,
+    NoTreeHead,
+    NotIncluded,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::BadSignature => write!(f, "tree head signature is invalid"),
+            VerifyError::TreeShrunk => write!(f, "tree head is older than the last one"),
+            VerifyError::Inconsistent => write!(f, "tree head is inconsistent with the last one"),
+            VerifyError::NoTreeHead => write!(f, "no tree head has been seen yet"),
+            VerifyError::NotIncluded => write!(f, "entry is not included in the tree"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+fn prefixed_hash(prefix: u8, data: &[&[u8]]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([prefix]);
+    for part in data {
+        hasher.update(part);
+    }
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&hasher.finalize());
+    Hash::from_bytes(&bytes)
+}
+
+/// Hash of a log entry as a leaf.
+pub fn leaf_hash(entry: &[u8]) -> Hash {
+    prefixed_hash(LEAF_PREFIX, &[entry])
+}
+
+/// Hash of an inner node of the log from the raw digests of its children.
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    prefixed_hash(NODE_PREFIX, &[&left.to_bytes(), &right.to_bytes()])
+}
+
+/// Parent of `children`, where a lone child is promoted as it is.
+fn node_children(children: &[Hash]) -> Hash {
+    if children.len() == 1 {
+        return children[0].clone();
+    }
+    node_hash(&children[0], &children[1])
+}
+
+/// Root of the tree over `leaves`.
+fn subtree_root(leaves: &[Hash]) -> Hash {
+    let levels = build_levels(leaves.to_vec(), 2, node_children);
+    levels[levels.len() - 1][0].clone()
+}
+
+/// Largest power of two smaller than `n`.
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+/// SUBPROOF of RFC 9162, section 2.1.4.1.
+fn subproof(m: usize, leaves: &[Hash], complete: bool, path: &mut Vec<Hash>) {
+    let n = leaves.len();
+    if m == n {
+        if !complete {
+            path.push(subtree_root(leaves));
+        }
+        return;
+    }
+    let k = split_point(n);
+    if m <= k {
+        subproof(m, &leaves[..k], complete, path);
+        path.push(subtree_root(&leaves[k..]));
+    } else {
+        subproof(m - k, &leaves[k..], false, path);
+        path.push(subtree_root(&leaves[..k]));
+    }
+}
+
+impl ConsistencyProof {
+    /// Check that `old_root` and `new_root` commit to trees of `old_size` and
+    /// `new_size` leaves where the first is a prefix of the second
+    /// (RFC 9162, section 2.1.4.2).
+    pub fn verify(&self, old_root: &Hash, new_root: &Hash) -> bool {
+        if self.old_size > self.new_size {
+            return false;
+        }
+        if self.old_size == self.new_size {
+            return self.path.is_empty() && old_root == new_root;
+        }
+        if self.old_size == 0 {
+            return self.path.is_empty();
+        }
+        let mut path = self.path.clone();
+        if self.old_size.is_power_of_two() {
+            path.insert(0, old_root.clone());
+        }
+        if path.is_empty() {
+            return false;
+        }
+        let mut fnode = self.old_size - 1;
+        let mut snode = self.new_size - 1;
+        while fnode & 1 == 1 {
+            fnode >>= 1;
+            snode >>= 1;
+        }
+        let mut fr = path[0].clone();
+        let mut sr = path[0].clone();
+        for c in &path[1..] {
+            if snode == 0 {
+                return false;
+            }
+            if fnode & 1 == 1 || fnode == snode {
+                fr = node_hash(c, &fr);
+                sr = node_hash(c, &sr);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                sr = node_hash(&sr, c);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+        &fr == old_root && &sr == new_root && snode == 0
+    }
+}
+
+/// Append-only log persisted in a directory. Entries are appended to
+/// `entries` with a length prefix and published tree heads to `heads`.
+#[derive(Debug)]
+pub struct TransparencyLog {
+    dir: PathBuf,
+    key: SigningKey,
+    entries: Vec<Vec<u8>>,
+    leaves: Vec<Hash>,
+    heads: Vec<SignedTreeHead>,
+}
+
+/// Read the key stored at `path`, or generate and store a new one.
+pub fn load_or_generate_key(path: &Path) -> io::Result<SigningKey> {
+    if path.exists() {
+        let bytes: [u8; 32] = fs::read(path)?
+            .try_into()
+            .map_err(|_| invalid_data("signing key must be 32 bytes"))?;
+        return Ok(SigningKey::from_bytes(&bytes));
+    }
+    let key = SigningKey::generate(&mut OsRng);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    // Only the owner may read the key.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    file.write_all(&key.to_bytes())?;
+    file.sync_all()?;
+    Ok(key)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn append(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(bytes)?;
+    file.sync_data()
+}
+
+impl TransparencyLog {
+    /// Open the log stored in `dir`, creating it when missing.
+    pub fn open(dir: &Path, key: SigningKey) -> io::Result<TransparencyLog> {
+        fs::create_dir_all(dir)?;
+        let mut entries = vec![];
+        let bytes = match fs::read(dir.join(ENTRIES_FILE)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+        let mut rest = bytes.as_slice();
+        while !rest.is_empty() {
+            if rest.len() < 4 {
+                return Err(invalid_data("truncated entry length"));
+            }
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            if rest.len() < 4 + len {
+                return Err(invalid_data("truncated entry"));
+            }
+            entries.push(rest[4..4 + len].to_vec());
+            rest = &rest[4 + len..];
+        }
+
+        let bytes = match fs::read(dir.join(HEADS_FILE)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+        if bytes.len() % SIGNED_HEAD_LEN != 0 {
+            return Err(invalid_data("truncated tree head"));
+        }
+        let heads: Vec<SignedTreeHead> = bytes
+            .chunks(SIGNED_HEAD_LEN)
+            .map(SignedTreeHead::from_bytes)
+            .collect();
+        if heads.iter().any(|sth| sth.head.tree_size > entries.len()) {
+            return Err(invalid_data("tree head is larger than the log"));
+        }
+
+        let log = TransparencyLog {
+            dir: dir.to_path_buf(),
+            key,
+            leaves: entries.iter().map(|entry| leaf_hash(entry)).collect(),
+            entries,
+            heads,
+        };
+        // Refuse heads signed with another key or not matching the entries.
+        let verifying_key = log.key.verifying_key();
+        for sth in &log.heads {
+            if !sth.verify(&verifying_key) {
+                return Err(invalid_data("tree head is not signed by the key"));
+            }
+            if sth.head.root != log.head_root(sth.head.tree_size) {
+                return Err(invalid_data("tree head does not match the entries"));
+            }
+        }
+        Ok(log)
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.key.verifying_key()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entry(&self, index: usize) -> Option<&[u8]> {
+        self.entries.get(index).map(Vec::as_slice)
+    }
+
+    /// Persist `entry` and return its index. It is covered by the next
+    /// published tree head.
+    pub fn append(&mut self, entry: &[u8]) -> io::Result<usize> {
+        let len = u32::try_from(entry.len()).map_err(|_| invalid_data("entry is too large"))?;
+        let mut bytes = len.to_be_bytes().to_vec();
+        bytes.extend_from_slice(entry);
+        append(&self.dir.join(ENTRIES_FILE), &bytes)?;
+        self.entries.push(entry.to_vec());
+        self.leaves.push(leaf_hash(entry));
+        Ok(self.entries.len() - 1)
+    }
+
+    /// Root of the first `tree_size` entries.
+    pub fn root(&self, tree_size: usize) -> Option<Hash> {
+        if tree_size == 0 || tree_size > self.leaves.len() {
+            return None;
+        }
+        Some(subtree_root(&self.leaves[..tree_size]))
+    }
+
+    /// Root signed in a tree head of `tree_size` entries, which is the hash of
+    /// no data for an empty log.
+    fn head_root(&self, tree_size: usize) -> Hash {
+        self.root(tree_size).unwrap_or_else(empty_root)
+    }
+
+    /// Sign and persist a tree head covering every entry.
+    pub fn publish(&mut self, timestamp: u64) -> io::Result<SignedTreeHead> {
+        let head = TreeHead {
+            tree_size: self.leaves.len(),
+            root: self.head_root(self.leaves.len()),
+            timestamp,
+        };
+        let sth = SignedTreeHead {
+            signature: self.key.sign(&head.to_bytes()),
+            head,
+        };
+        append(&self.dir.join(HEADS_FILE), &sth.to_bytes())?;
+        self.heads.push(sth.clone());
+        Ok(sth)
+    }
+
+    /// Publish a tree head when there are new entries and the last head is
+    /// at least `interval` milliseconds old.
+    pub fn publish_if_due(
+        &mut self,
+        now: u64,
+        interval: u64,
+    ) -> io::Result<Option<SignedTreeHead>> {
+        let due = match self.latest_head() {
+            Some(sth) => {
+                sth.head.tree_size < self.leaves.len()
+                    && now.saturating_sub(sth.head.timestamp) >= interval
+            }
+            None => !self.leaves.is_empty(),
+        };
+        if due {
+            self.publish(now).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn latest_head(&self) -> Option<&SignedTreeHead> {
+        self.heads.last()
+    }
+
+    /// Inclusion proof of the entry at `index` in the tree of `tree_size` entries.
+    pub fn inclusion_proof(&self, index: usize, tree_size: usize) -> Option<MerkleProof> {
+        if tree_size > self.leaves.len() || tree_size == 0 {
+            return None;
+        }
+        let levels = build_levels(self.leaves[..tree_size].to_vec(), 2, node_children);
+        binary_proof(&levels, index)
+    }
+
+    /// Consistency proof between the trees of `old_size` and `new_size` entries.
+    pub fn consistency_proof(&self, old_size: usize, new_size: usize) -> Option<ConsistencyProof> {
+        if old_size > new_size || new_size > self.leaves.len() {
+            return None;
+        }
+        let mut path = vec![];
+        if 0 < old_size && old_size < new_size {
+            subproof(old_size, &self.leaves[..new_size], true, &mut path);
+        }
+        Some(ConsistencyProof {
+            old_size,
+            new_size,
+            path,
+        })
+    }
+}
+
+impl MerkleProof {
+    /// Check that `entry` is at `index` of the log tree committed by `root`.
+    pub fn verify_entry(&self, entry: &[u8], root: &Hash) -> bool {
+        self.is_well_formed() && &self.root_with(&leaf_hash(entry), node_hash) == root
+    }
+}
+
+/// Client of a log. It only accepts tree heads signed by the log and
+/// consistent with the last one it has seen.
+#[derive(Debug)]
+pub struct LogVerifier {
+    key: VerifyingKey,
+    last: Option<SignedTreeHead>,
+}
+
+impl LogVerifier {
+    pub fn new(key: VerifyingKey) -> LogVerifier {
+        LogVerifier { key, last: None }
+    }
+
+    pub fn last_head(&self) -> Option<&SignedTreeHead> {
+        self.last.as_ref()
+    }
+
+    /// Accept `sth` when its signature is valid and `proof` shows that it
+    /// extends the last tree head. The proof is not used for the first head.
+    pub fn update(
+        &mut self,
+        sth: SignedTreeHead,
+        proof: &ConsistencyProof,
+    ) -> Result<(), VerifyError> {
+        if !sth.verify(&self.key) {
+            return Err(VerifyError::BadSignature);
+        }
+        if let Some(last) = &self.last {
+            if sth.head.tree_size < last.head.tree_size {
+                return Err(VerifyError::TreeShrunk);
+            }
+            if proof.old_size != last.head.tree_size
+                || proof.new_size != sth.head.tree_size
+                || !proof.verify(&last.head.root, &sth.head.root)
+            {
+                return Err(VerifyError::Inconsistent);
+            }
+        }
+        self.last = Some(sth);
+        Ok(())
+    }
+
+    /// Check that `entry` is included in the last accepted tree head.
+    pub fn verify_inclusion(&self, entry: &[u8], proof: &MerkleProof) -> Result<(), VerifyError> {
+        let last = self.last.as_ref().ok_or(VerifyError::NoTreeHead)?;
+        if proof.tree_size == last.head.tree_size && proof.verify_entry(entry, &last.head.root) {
+            Ok(())
+        } else {
+            Err(VerifyError::NotIncluded)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    #[test]
+    fn consistency_proofs() {
+        let leaves: Vec<Hash> = (0..20).map(|i| leaf_hash(&[i])).collect();
+        for new_size in 1..=leaves.len() {
+            let new_root = subtree_root(&leaves[..new_size]);
+            for old_size in 1..=new_size {
+                let old_root = subtree_root(&leaves[..old_size]);
+                let mut path = vec![];
+                if old_size < new_size {
+                    subproof(old_size, &leaves[..new_size], true, &mut path);
+                }
+                let proof = ConsistencyProof {
+                    old_size,
+                    new_size,
+                    path,
+                };
+                assert!(
+                    proof.verify(&old_root, &new_root),
+                    "{} {}",
+                    old_size,
+                    new_size
+                );
+                if old_size < new_size {
+                    assert!(!proof.verify(&leaf_hash(b"fork"), &new_root));
+                    assert!(!proof.verify(&old_root, &leaf_hash(b"fork")));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nodes_are_not_entries() {
+        let leaves: Vec<Hash> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|entry| leaf_hash(entry.as_bytes()))
+            .collect();
+        let root = subtree_root(&leaves);
+        let levels = build_levels(leaves.clone(), 2, node_children);
+        let honest = binary_proof(&levels, 2).unwrap();
+        assert!(honest.verify_entry(b"c", &root));
+
+        // Present the left node as an entry of a tree of two leaves.
+        let proof = MerkleProof {
+            tree_size: 2,
+            index: 0,
+            path: vec![honest.path[1].clone()],
+        };
+        let mut preimage = vec![NODE_PREFIX];
+        preimage.extend_from_slice(&leaves[0].to_bytes());
+        preimage.extend_from_slice(&leaves[1].to_bytes());
+        assert!(!proof.verify_entry(&preimage, &root));
+        assert!(!proof.verify_entry(&preimage[1..], &root));
+        let hex = format!("{}{}", leaves[0].value(), leaves[1].value());
+        assert!(!proof.verify_entry(hex.as_bytes(), &root));
+        assert_eq!(node_hash(&leaves[0], &leaves[1]), levels[1][0]);
+    }
+
+    #[test]
+    fn generated_key() {
+        let dir = TempDir::new("log_key");
+        let path = dir.join("key");
+        let key = load_or_generate_key(&path).unwrap();
+        assert_eq!(load_or_generate_key(&path).unwrap(), key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn persist_and_reopen() {
+        let dir = TempDir::new("log_reopen");
+        let mut log = TransparencyLog::open(&dir, key()).unwrap();
+        for i in 0..5u8 {
+            assert_eq!(log.append(&[i]).unwrap(), i as usize);
+        }
+        let sth = log.publish(1000).unwrap();
+        assert!(sth.verify(&log.verifying_key()));
+        let root = log.root(5).unwrap();
+        drop(log);
+
+        let log = TransparencyLog::open(&dir, key()).unwrap();
+        assert_eq!(log.len(), 5);
+        assert_eq!(log.entry(3), Some(&[3u8][..]));
+        assert_eq!(log.root(5), Some(root));
+        assert_eq!(log.latest_head(), Some(&sth));
+        drop(log);
+
+        let error = TransparencyLog::open(&dir, SigningKey::from_bytes(&[8u8; 32])).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // Swap two entries of the same length.
+        let path = dir.join(ENTRIES_FILE);
+        let mut entries = fs::read(&path).unwrap();
+        entries.swap(4, 9);
+        fs::write(&path, &entries).unwrap();
+        assert!(TransparencyLog::open(&dir, key()).is_err());
+        entries.swap(4, 9);
+        fs::write(&path, &entries).unwrap();
+
+        // Move the timestamp of the head.
+        let path = dir.join(HEADS_FILE);
+        let mut heads = fs::read(&path).unwrap();
+        heads[47] ^= 1;
+        fs::write(&path, &heads).unwrap();
+        assert!(TransparencyLog::open(&dir, key()).is_err());
+    }
+
+    #[test]
+    fn publish_periodically() {
+        let dir = TempDir::new("log_periodic");
+        let mut log = TransparencyLog::open(&dir, key()).unwrap();
+        assert_eq!(log.publish_if_due(0, 100).unwrap(), None);
+        log.append(b"a").unwrap();
+        assert!(log.publish_if_due(0, 100).unwrap().is_some());
+        log.append(b"b").unwrap();
+        assert_eq!(log.publish_if_due(50, 100).unwrap(), None);
+        let sth = log.publish_if_due(100, 100).unwrap().unwrap();
+        assert_eq!(sth.head.tree_size, 2);
+        assert_eq!(log.publish_if_due(500, 100).unwrap(), None);
+    }
+
+    #[test]
+    fn client_tracks_tree_heads() {
+        let dir = TempDir::new("log_client");
+        let mut log = TransparencyLog::open(&dir, key()).unwrap();
+        let mut client = LogVerifier::new(log.verifying_key());
+        assert_eq!(
+            client.verify_inclusion(
+                b"a",
+                &MerkleProof {
+                    tree_size: 0,
+                    index: 0,
+                    path: vec![],
+                }
+            ),
+            Err(VerifyError::NoTreeHead)
+        );
+
+        for entry in ["a", "b", "c"] {
+            log.append(entry.as_bytes()).unwrap();
+        }
+        let first = log.publish(1).unwrap();
+        client
+            .update(first.clone(), &log.consistency_proof(0, 3).unwrap())
+            .unwrap();
+
+        for entry in ["d", "e", "f", "g"] {
+            log.append(entry.as_bytes()).unwrap();
+        }
+        let second = log.publish(2).unwrap();
+        let proof = log.consistency_proof(3, 7).unwrap();
+        client.update(second.clone(), &proof).unwrap();
+        assert_eq!(client.last_head(), Some(&second));
+
+        let inclusion = log.inclusion_proof(5, 7).unwrap();
+        assert_eq!(client.verify_inclusion(b"f", &inclusion), Ok(()));
+        assert_eq!(
+            client.verify_inclusion(b"x", &inclusion),
+            Err(VerifyError::NotIncluded)
+        );
+
+        // An older head is refused.
+        assert_eq!(client.update(first, &proof), Err(VerifyError::TreeShrunk));
+
+        // A head signed by another key is refused.
+        let mut forged = second.clone();
+        forged.signature = SigningKey::from_bytes(&[8u8; 32]).sign(&forged.head.to_bytes());
+        assert_eq!(
+            client.update(forged, &log.consistency_proof(7, 7).unwrap()),
+            Err(VerifyError::BadSignature)
+        );
+
+        // A properly signed head of a forked log is refused.
+        let fork_dir = TempDir::new("log_fork");
+        let mut fork = TransparencyLog::open(&fork_dir, key()).unwrap();
+        for entry in ["a", "b", "c", "x", "e", "f", "g", "h"] {
+            fork.append(entry.as_bytes()).unwrap();
+        }
+        let forked = fork.publish(3).unwrap();
+        assert_eq!(
+            client.update(forked, &fork.consistency_proof(7, 8).unwrap()),
+            Err(VerifyError::Inconsistent)
+        );
+        assert_eq!(client.last_head(), Some(&second));
+
+    }
+}
@@ -0,0 +1,230 @@
+use crate::proof::{fold_wide_path, kary_proof, wide_children, KaryProof};
+use crate::{build_levels, empty_root, gen_hash, hash_children, Hash};
+use std::fmt::Debug;
+
+/// Number of children of the nodes of a `VerkleTree`.
+pub const WIDTH: usize = 256;
+
+/// Vector commitment to the children of a node. A commitment is opened at one
+/// position to prove a single child without revealing the others, which is
+/// what lets polynomial schemes keep proofs small for wide nodes.
+pub trait CommitmentScheme {
+    type Commitment: Clone + Debug + PartialEq;
+    type Opening: Clone + Debug + PartialEq;
+
+    fn commit(&self, children: &[Hash]) -> Self::Commitment;
+
+    /// Open the commitment to `children` at `index`.
+    fn open(&self, children: &[Hash], index: usize) -> Self::Opening;
+
+    /// Check that `child` is at `index` of the `width` children committed by
+    /// `commitment`.
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        width: usize,
+        index: usize,
+        child: &Hash,
+        opening: &Self::Opening,
+    ) -> bool;
+
+    /// Hash of a commitment, which becomes a child of the parent node.
+    fn commitment_hash(&self, commitment: &Self::Commitment) -> Hash;
+}
+
+/// Commitment by hashing all the children, as the nodes of `Tree` do. An
+/// opening is made of all the other children.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HashCommitment;
+
+impl CommitmentScheme for HashCommitment {
+    type Commitment = Hash;
+    type Opening = Vec<Hash>;
+
+    fn commit(&self, children: &[Hash]) -> Hash {
+        hash_children(children)
+    }
+
+    fn open(&self, children: &[Hash], index: usize) -> Vec<Hash> {
+        let mut siblings = children.to_vec();
+        siblings.remove(index);
+        siblings
+    }
+
+    fn verify(
+        &self,
+        commitment: &Hash,
+        width: usize,
+        index: usize,
+        child: &Hash,
+        opening: &Vec<Hash>,
+    ) -> bool {
+        if opening.len() + 1 != width || index >= width {
+            return false;
+        }
+        let mut children = opening.clone();
+        children.insert(index, child.clone());
+        &hash_children(&children) == commitment
+    }
+
+    fn commitment_hash(&self, commitment: &Hash) -> Hash {
+        commitment.clone()
+    }
+}
+
+/// Proof of the leaf at `index`. `steps` hold, from the leaves up, the
+/// commitment of every node on the path with its opening. Promoted nodes have
+/// no step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerkleProof<C, O> {
+    pub tree_size: usize,
+    pub index: usize,
+    pub steps: Vec<Option<(C, O)>>,
+}
+
+/// Tree of `WIDTH` children per node committed with a `CommitmentScheme`.
+#[derive(Debug)]
+pub struct VerkleTree<S: CommitmentScheme> {
+    scheme: S,
+    levels: Vec<Vec<Hash>>,
+    root: Hash,
+}
+
+impl<S: CommitmentScheme> VerkleTree<S> {
+    pub fn new(scheme: S, data: Vec<&str>) -> VerkleTree<S> {
+        let leaves = data
+            .iter()
+            .map(|datum| gen_hash(datum.to_string()))
+            .collect();
+        VerkleTree::from_hashes(scheme, leaves)
+    }
+
+    pub fn from_hashes(scheme: S, leaves: Vec<Hash>) -> VerkleTree<S> {
+        let levels = build_levels(leaves, WIDTH, |children: &[Hash]| {
+            if children.len() == 1 {
+                children[0].clone()
+            } else {
+                scheme.commitment_hash(&scheme.commit(children))
+            }
+        });
+        let root = levels[levels.len() - 1]
+            .first()
+            .cloned()
+            .unwrap_or_else(empty_root);
+        VerkleTree {
+            scheme,
+            levels,
+            root,
+        }
+    }
+
+    /// Root of the tree, the hash of no data when it has no leaves.
+    pub fn root(&self) -> &Hash {
+        &self.root
+    }
+
+    pub fn get_proof(&self, index: usize) -> Option<VerkleProof<S::Commitment, S::Opening>> {
+        let steps = wide_children(&self.levels, WIDTH, index)?
+            .into_iter()
+            .map(|(i, children)| {
+                (children.len() > 1)
+                    .then(|| (self.scheme.commit(children), self.scheme.open(children, i)))
+            })
+            .collect();
+        Some(VerkleProof {
+            tree_size: self.levels[0].len(),
+            index,
+            steps,
+        })
+    }
+}
+
+impl VerkleTree<HashCommitment> {
+    /// Proof of the leaf at `index`, the same as for a `Tree` of arity
+    /// `WIDTH`.
+    pub fn get_kary_proof(&self, index: usize) -> Option<KaryProof> {
+        kary_proof(&self.levels, WIDTH, index)
+    }
+}
+
+impl<C, O> VerkleProof<C, O> {
+    /// Check that `leaf` is at `index` of the tree committed by `root`.
+    pub fn verify<S>(&self, scheme: &S, leaf: &Hash, root: &Hash) -> bool
+    where
+        S: CommitmentScheme<Commitment = C, Opening = O>,
+    {
+        let computed = fold_wide_path(
+            WIDTH,
+            self.tree_size,
+            self.index,
+            &self.steps,
+            leaf,
+            |i, width, child, step| match step {
+                None if width == 1 => Some(child.clone()),
+                Some((commitment, opening)) if width > 1 => scheme
+                    .verify(commitment, width, i, child, opening)
+                    .then(|| scheme.commitment_hash(commitment)),
+                _ => None,
+            },
+        );
+        computed.as_ref() == Some(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{build, data};
+
+    #[test]
+    fn hash_scheme_matches_kary_tree() {
+        for size in [1, 2, 256, 257, 1000] {
+            let data = data(size);
+            let verkle = VerkleTree::new(HashCommitment, data.iter().map(String::as_str).collect());
+            let tree = build(&data, WIDTH);
+            assert_eq!(verkle.root(), &tree.root);
+
+            let index = size - 1;
+            let proof = verkle.get_proof(index).unwrap();
+            let openings: Vec<Vec<Hash>> = proof
+                .steps
+                .iter()
+                .map(|step| step.as_ref().map_or(vec![], |(_, opening)| opening.clone()))
+                .collect();
+            let kary = tree.get_kary_proof(index).unwrap();
+            assert_eq!(openings, kary.levels);
+            assert_eq!(verkle.get_kary_proof(index), Some(kary.clone()));
+            assert!(kary.verify(&gen_hash(data[index].clone()), verkle.root()));
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let verkle = VerkleTree::new(HashCommitment, vec![]);
+        assert_eq!(verkle.root(), &build(&[], WIDTH).root);
+        assert_eq!(verkle.root(), &empty_root());
+        assert!(verkle.get_proof(0).is_none());
+        assert!(verkle.get_kary_proof(0).is_none());
+    }
+
+    #[test]
+    fn proofs() {
+        let data = data(600);
+        let tree = VerkleTree::new(HashCommitment, data.iter().map(String::as_str).collect());
+        for index in [0, 255, 256, 599] {
+            let proof = tree.get_proof(index).unwrap();
+            let leaf = gen_hash(data[index].clone());
+            assert!(proof.verify(&HashCommitment, &leaf, tree.root()));
+            assert!(!proof.verify(&HashCommitment, &gen_hash("x".to_string()), tree.root()));
+        }
+        assert!(tree.get_proof(600).is_none());
+
+        let leaf = gen_hash(data[599].clone());
+        let mut moved = tree.get_proof(599).unwrap();
+        moved.index = 598;
+        assert!(!moved.verify(&HashCommitment, &leaf, tree.root()));
+        let mut truncated = tree.get_proof(599).unwrap();
+        truncated.steps.pop();
+        assert!(!truncated.verify(&HashCommitment, &leaf, tree.root()));
+    }
+}
@@ -0,0 +1,296 @@
+//! Proof verification without `std` nor allocation. Hashes are raw 32 bytes
+//! digests and proofs are read from their binary encoding as they are.
+
+use core::fmt;
+use sha2::{Digest, Sha256};
+
+/// Version byte of the binary proof encoding.
+pub const PROOF_VERSION: u8 = 1;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Side of the sibling relative to the path being proven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    UnsupportedVersion(u8),
+    UnexpectedEof,
+    VarintOverflow,
+    NonCanonicalVarint,
+    IndexOutOfRange,
+    DirectionMismatch,
+    NonZeroPadding,
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported proof version: {}", v),
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of proof"),
+            DecodeError::VarintOverflow => write!(f, "varint overflows u64"),
+            DecodeError::NonCanonicalVarint => write!(f, "varint is not minimally encoded"),
+            DecodeError::IndexOutOfRange => write!(f, "leaf index is out of the tree"),
+            DecodeError::DirectionMismatch => {
+                write!(f, "directions do not match the leaf index")
+            }
+            DecodeError::NonZeroPadding => write!(f, "padding bits of the bitmap are set"),
+            DecodeError::TrailingBytes => write!(f, "trailing bytes after proof"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// SHA256 digest of a leaf, the raw counterpart of `gen_hash`.
+pub fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(&Sha256::digest(data));
+    ret
+}
+
+/// Digest of a parent node, the raw counterpart of `hash_pair`. Parents hash
+/// the hex digests of their children, encoded here on the stack.
+pub fn pair_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hex = [0u8; 128];
+    for (i, byte) in left.iter().chain(right).enumerate() {
+        hex[2 * i] = HEX_DIGITS[(byte >> 4) as usize];
+        hex[2 * i + 1] = HEX_DIGITS[(byte & 0x0f) as usize];
+    }
+    leaf_hash(&hex)
+}
+
+/// Sides of the siblings on the path of `index` in a tree of `tree_size`
+/// leaves, from the leaf up. Promoted nodes have no sibling.
+#[derive(Debug, Clone)]
+pub struct Positions {
+    len: usize,
+    i: usize,
+}
+
+impl Positions {
+    pub fn new(tree_size: usize, index: usize) -> Positions {
+        Positions {
+            len: tree_size,
+            i: index,
+        }
+    }
+}
+
+impl Iterator for Positions {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Position> {
+        while self.len > 1 {
+            let (i, len) = (self.i, self.len);
+            self.i /= 2;
+            self.len = len.div_ceil(2);
+            if i % 2 == 1 {
+                return Some(Position::Left);
+            } else if i + 1 < len {
+                return Some(Position::Right);
+            }
+        }
+        None
+    }
+}
+
+/// Read an unsigned LEB128 varint and advance `bytes` past it.
+pub(crate) fn read_varint(bytes: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut value: u64 = 0;
+    for i in 0..10 {
+        let (&byte, rest) = bytes.split_first().ok_or(DecodeError::UnexpectedEof)?;
+        *bytes = rest;
+        let bits = (byte & 0x7f) as u64;
+        if i == 9 && bits > 1 {
+            return Err(DecodeError::VarintOverflow);
+        }
+        value |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            if i > 0 && bits == 0 {
+                return Err(DecodeError::NonCanonicalVarint);
+            }
+            return Ok(value);
+        }
+    }
+    Err(DecodeError::VarintOverflow)
+}
+
+/// Binary encoded proof whose encoding has been checked, borrowing its
+/// siblings from the encoded bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodedProof<'a> {
+    pub tree_size: usize,
+    pub index: usize,
+    siblings: &'a [u8],
+}
+
+impl<'a> EncodedProof<'a> {
+    /// Parse `version | varint(tree_size) | varint(index) | siblings | bitmap`.
+    /// Anything but the exact canonical encoding is rejected.
+    pub fn parse(proof: &'a [u8]) -> Result<EncodedProof<'a>, DecodeError> {
+        let (&version, mut rest) = proof.split_first().ok_or(DecodeError::UnexpectedEof)?;
+        if version != PROOF_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let tree_size = read_varint(&mut rest)?;
+        let index = read_varint(&mut rest)?;
+        if index >= tree_size {
+            return Err(DecodeError::IndexOutOfRange);
+        }
+        let tree_size = usize::try_from(tree_size).map_err(|_| DecodeError::VarintOverflow)?;
+        let index = index as usize;
+
+        let len = Positions::new(tree_size, index).count();
+        let bitmap_len = len.div_ceil(8);
+        if rest.len() < len * 32 + bitmap_len {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        if rest.len() > len * 32 + bitmap_len {
+            return Err(DecodeError::TrailingBytes);
+        }
+        let (siblings, bitmap) = rest.split_at(len * 32);
+        for (i, position) in Positions::new(tree_size, index).enumerate() {
+            let is_left = bitmap[i / 8] & (1 << (i % 8)) != 0;
+            if is_left != (position == Position::Left) {
+                return Err(DecodeError::DirectionMismatch);
+            }
+        }
+        if !len.is_multiple_of(8) && bitmap[bitmap_len - 1] >> (len % 8) != 0 {
+            return Err(DecodeError::NonZeroPadding);
+        }
+        Ok(EncodedProof {
+            tree_size,
+            index,
+            siblings,
+        })
+    }
+
+    /// Siblings from the leaf up with their sides.
+    pub fn siblings(&self) -> impl Iterator<Item = (Position, [u8; 32])> + 'a {
+        Positions::new(self.tree_size, self.index)
+            .zip(self.siblings.chunks_exact(32))
+            .map(|(position, sibling)| (position, sibling.try_into().unwrap()))
+    }
+
+    /// Recompute the root from the digest of the proven leaf.
+    pub fn root(&self, leaf: &[u8; 32]) -> [u8; 32] {
+        self.siblings()
+            .fold(*leaf, |ret, (position, sibling)| match position {
+                Position::Left => pair_hash(&sibling, &ret),
+                Position::Right => pair_hash(&ret, &sibling),
+            })
+    }
+}
+
+/// Check an encoded proof and recompute the root from the digest of the
+/// proven leaf.
+pub fn proof_root(proof: &[u8], leaf: &[u8; 32]) -> Result<[u8; 32], DecodeError> {
+    Ok(EncodedProof::parse(proof)?.root(leaf))
+}
+
+/// Check that `data` is the leaf proven by the encoded `proof` in the tree
+/// committed by `root`.
+pub fn verify_proof(proof: &[u8], data: &[u8], root: &[u8; 32]) -> Result<bool, DecodeError> {
+    Ok(&proof_root(proof, &leaf_hash(data))? == root)
+}
+
+#[cfg(test)]
+mod vectors {
+    use super::*;
+
+    // Tree of "a" to "e" and the proof of "c", as built with `std`.
+    const ROOT: &str = "dea979f026a014fcb2300d6300e73ae1ccfb0dd238835d33895286d610eb7c4f";
+    const PROOF: &str = "01050218ac3e7343f016890c510e93f935261169d9e3f565436429830faf0934\
+                         f4f8e462af5c3cb8da3e4f25061e829ebeea5c7513c54949115b1acc225930a9\
+                         0154da3f79bb7b435b05321651daefd374cdc681dc06faa65e374e38337b88ca\
+                         046dea02";
+
+    fn decode<const N: usize>(hex: &str) -> [u8; N] {
+        let digit = |c: u8| (c as char).to_digit(16).unwrap() as u8;
+        let mut ret = [0u8; N];
+        for (byte, pair) in ret.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+            *byte = digit(pair[0]) << 4 | digit(pair[1]);
+        }
+        ret
+    }
+
+    #[test]
+    fn known_proof() {
+        let root = decode::<32>(ROOT);
+        let proof = decode::<100>(PROOF);
+        assert_eq!(verify_proof(&proof, b"c", &root), Ok(true));
+        assert_eq!(verify_proof(&proof, b"d", &root), Ok(false));
+        let parsed = EncodedProof::parse(&proof).unwrap();
+        assert_eq!((parsed.tree_size, parsed.index), (5, 2));
+        assert_eq!(
+            verify_proof(&proof[..99], b"c", &root),
+            Err(DecodeError::UnexpectedEof)
+        );
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::testing::{build, data};
+    use crate::Tree;
+
+    #[test]
+    fn same_hashes_as_tree() {
+        let left = crate::gen_hash("a".to_string());
+        let right = crate::gen_hash("b".to_string());
+        assert_eq!(leaf_hash(b"a"), left.to_bytes());
+        assert_eq!(
+            pair_hash(&left.to_bytes(), &right.to_bytes()),
+            crate::hash_pair(&left, &right).to_bytes()
+        );
+    }
+
+    #[test]
+    fn verify_encoded() {
+        for size in 1..20 {
+            let data = data(size);
+            let tree = build(&data, 2);
+            let root = tree.root.to_bytes();
+            for (index, datum) in data.iter().enumerate() {
+                let proof = tree.get_proof(index).unwrap().encode();
+                assert_eq!(verify_proof(&proof, datum.as_bytes(), &root), Ok(true));
+                assert_eq!(verify_proof(&proof, b"forged", &root), Ok(false));
+            }
+        }
+    }
+
+    #[test]
+    fn reject_malformed() {
+        let mut tree = Tree::new(vec!["a", "b", "c", "d", "e"]);
+        tree.build_tree();
+        let root = tree.root.to_bytes();
+        let proof = tree.get_proof(2).unwrap().encode();
+
+        let mut truncated = proof.clone();
+        truncated.pop();
+        assert_eq!(
+            verify_proof(&truncated, b"c", &root),
+            Err(DecodeError::UnexpectedEof)
+        );
+        let mut flipped = proof.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify_proof(&flipped, b"c", &root),
+            Err(DecodeError::DirectionMismatch)
+        );
+        let mut trailing = proof;
+        trailing.push(0);
+        assert_eq!(
+            verify_proof(&trailing, b"c", &root),
+            Err(DecodeError::TrailingBytes)
+        );
+    }
+}
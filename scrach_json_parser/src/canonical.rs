@@ -0,0 +1,260 @@
+//! Canonical JSON as defined by RFC 8785 (JCS), for hashing and signing.
+
+use crate::number::Decimal;
+use crate::serializer::{write_string, SerializeError};
+use crate::{Number, Value};
+use std::io;
+
+/// Write `value` in its canonical form: no whitespace, object keys ordered by
+/// their UTF-16 code units, numbers formatted as ECMAScript does and strings
+/// with only the escapes JSON requires.
+pub fn write<W: io::Write>(writer: &mut W, value: &Value) -> Result<(), SerializeError> {
+    match value {
+        Value::Null => writer.write_all(b"null")?,
+        Value::Boolean(b) => write!(writer, "{}", b)?,
+        Value::Number(n) => {
+            let text = ecmascript_number(double(n)?);
+            // A kept literal must be the value of the double as well.
+            if let Some(literal) = n.as_str() {
+                if Decimal::parse(literal) != Decimal::parse(&text) {
+                    return Err(SerializeError::InexactNumber(n.clone()));
+                }
+            }
+            writer.write_all(text.as_bytes())?;
+        }
+        Value::String(s) => write_string(writer, s)?,
+        Value::Array(array) => {
+            writer.write_all(b"[")?;
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                write(writer, element)?;
+            }
+            writer.write_all(b"]")?;
+        }
+        Value::Object(object) => {
+            // Sorting by UTF-8 bytes would differ from UTF-16 above the Basic
+            // Multilingual Plane.
+            let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            writer.write_all(b"{")?;
+            for (i, (key, element)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                write_string(writer, key)?;
+                writer.write_all(b":")?;
+                write(writer, element)?;
+            }
+            writer.write_all(b"}")?;
+        }
+    }
+    Ok(())
+}
+
+pub fn to_string(value: &Value) -> Result<String, SerializeError> {
+    let mut bytes = vec![];
+    write(&mut bytes, value)?;
+    Ok(String::from_utf8(bytes).expect("serializer writes UTF-8"))
+}
+
+/// Convert `n` to the IEEE 754 double JCS numbers are. Integers must read back
+/// from the double unchanged, or distinct integers would share a canonical
+/// form.
+fn double(n: &Number) -> Result<f64, SerializeError> {
+    let f = n.to_f64();
+    let exact = if let Some(u) = n.as_u64() {
+        f as u128 == u as u128
+    } else if let Some(i) = n.as_i64() {
+        f as i128 == i as i128
+    } else if f.is_finite() {
+        true
+    } else {
+        return Err(SerializeError::NonFiniteNumber(f));
+    };
+    if exact {
+        Ok(f)
+    } else {
+        Err(SerializeError::InexactNumber(n.clone()))
+    }
+}
+
+/// Format a finite `f` as ECMAScript's `Number.prototype.toString` does.
+fn ecmascript_number(f: f64) -> String {
+    if f == 0.0 {
+        return "0".to_string();
+    }
+    // The shortest digits that read back as `f` only give their count: when
+    // two are as close to `f`, ECMAScript takes the even one, so round `f`
+    // exactly to that many digits, as in `d.ddde-x`.
+    let shortest = format!("{:e}", f.abs());
+    let precision = shortest.split_once('e').unwrap().0.len().saturating_sub(2);
+    let scientific = format!("{:.*e}", precision, f.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let digits = digits.trim_end_matches('0');
+    let k = digits.len() as i32;
+    // The value is 0.digits * 10^n.
+    let n = exponent.parse::<i32>().unwrap() + 1;
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n > 0 { '+' } else { '-' };
+        let fraction = if k == 1 {
+            String::new()
+        } else {
+            format!(".{}", &digits[1..])
+        };
+        format!("{}{}e{}{}", &digits[..1], fraction, sign, (n - 1).abs())
+    };
+    if f < 0.0 {
+        format!("-{}", body)
+    } else {
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn rfc_numbers() {
+        // Appendix B of RFC 8785.
+        for (bits, expected) in [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ] {
+            let value = Value::Number(Number::from(f64::from_bits(bits)));
+            assert_eq!(to_string(&value).unwrap(), expected, "{:016x}", bits);
+        }
+        for f in [f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                to_string(&Value::Number(Number::from(f))),
+                Err(SerializeError::NonFiniteNumber(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn inexact_numbers() {
+        for (json, expected) in [
+            ("9007199254740992", "9007199254740992"),
+            ("-9007199254740992", "-9007199254740992"),
+            ("9007199254740994", "9007199254740994"),
+            ("10000000000000000000", "10000000000000000000"),
+            ("1e19", "10000000000000000000"),
+            ("-9223372036854775808", "-9223372036854776000"),
+            ("0.1", "0.1"),
+        ] {
+            assert_eq!(to_string(&from_str(json).unwrap()).unwrap(), expected);
+        }
+        for json in [
+            "9007199254740993",
+            "-9007199254740993",
+            "18446744073709551615",
+            "9223372036854775807",
+        ] {
+            assert!(
+                matches!(
+                    to_string(&from_str(json).unwrap()),
+                    Err(SerializeError::InexactNumber(_))
+                ),
+                "{}",
+                json
+            );
+        }
+        let literals = crate::from_str_arbitrary_precision(
+            "[1.50, 1E+2, 123456789012345678901234567890, 0.1000000000000000000000001]",
+        )
+        .unwrap();
+        let Value::Array(literals) = literals else {
+            unreachable!()
+        };
+        assert_eq!(to_string(&literals[0]).unwrap(), "1.5");
+        assert_eq!(to_string(&literals[1]).unwrap(), "100");
+        for inexact in &literals[2..] {
+            assert!(matches!(
+                to_string(inexact),
+                Err(SerializeError::InexactNumber(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rfc_example() {
+        // Section 3.2.2 of RFC 8785.
+        let value = from_str(
+            r#"{
+              "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+              "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+              "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_string(&value).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn rfc_key_order() {
+        // Section 3.2.3 of RFC 8785.
+        let value = from_str(
+            r#"{
+              "\u20ac": "Euro Sign",
+              "\r": "Carriage Return",
+              "\ufb33": "Hebrew Letter Dalet With Dagesh",
+              "1": "One",
+              "\ud83d\ude00": "Emoji: Grinning Face",
+              "\u0080": "Control",
+              "\u00f6": "Latin Small Letter O With Diaeresis"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_string(&value).unwrap(),
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+             \"ö\":\"Latin Small Letter O With Diaeresis\",\"€\":\"Euro Sign\",\
+             \"😀\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+    }
+
+    #[test]
+    fn same_for_equal_values() {
+        let a = from_str(r#"{"b": [1.0, 10, 1e2], "a": "x"}"#).unwrap();
+        let b = from_str(r#"{ "a" : "x", "b" : [1, 1E1, 100.0] }"#).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(to_string(&a).unwrap(), to_string(&b).unwrap());
+        assert_eq!(to_string(&a).unwrap(), r#"{"a":"x","b":[1,10,100]}"#);
+    }
+}
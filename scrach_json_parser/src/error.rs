@@ -0,0 +1,126 @@
+use crate::lexer::LexerError;
+use crate::parser::ParserError;
+use std::fmt;
+
+/// Error of `from_str`, raised by either the lexer or the parser.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Lexer(LexerError),
+    Parser(ParserError),
+}
+
+impl Error {
+    pub fn message(&self) -> &str {
+        match self {
+            Error::Lexer(e) => &e.message,
+            Error::Parser(e) => &e.message,
+        }
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::Lexer(e) => e.location.as_ref(),
+            Error::Parser(e) => e.location.as_ref(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Lexer(e) => write!(f, "{}", e),
+            Error::Parser(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Lexer(e) => Some(e),
+            Error::Parser(e) => Some(e),
+        }
+    }
+}
+
+impl From<LexerError> for Error {
+    fn from(e: LexerError) -> Error {
+        Error::Lexer(e)
+    }
+}
+
+impl From<ParserError> for Error {
+    fn from(e: ParserError) -> Error {
+        Error::Parser(e)
+    }
+}
+
+/// Place of an error in the source: a byte offset with its line and column,
+/// both starting at 1, and the text of the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+}
+
+impl Location {
+    pub fn new(source: &str, offset: usize) -> Location {
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        Location {
+            offset,
+            line: source[..offset].matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+            snippet: source[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    /// `line L, column C` followed by the line and a caret under the column.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Keep the tabs of the line so that the caret lines up.
+        let padding: String = self
+            .snippet
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        write!(
+            f,
+            "line {}, column {}\n{}\n{}^",
+            self.line, self.column, self.snippet, padding
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_and_column() {
+        let source = "{\n  \"名前\": tru\n}";
+        let location = Location::new(source, source.find("tru").unwrap());
+        assert_eq!(location.line, 2);
+        assert_eq!(location.column, 9);
+        assert_eq!(location.snippet, "  \"名前\": tru");
+        assert_eq!(
+            location.to_string(),
+            "line 2, column 9\n  \"名前\": tru\n        ^"
+        );
+
+        let end = Location::new(source, source.len());
+        assert_eq!((end.line, end.column), (3, 2));
+    }
+}
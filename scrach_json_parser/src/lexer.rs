@@ -1,10 +1,8 @@
-#[derive(Debug, PartialEq)]
-enum Scope {
-    Global,
-    String,
-}
+use crate::error::Location;
+use crate::Number;
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     LeftBrace,
     RightBrace,
@@ -14,126 +12,313 @@ pub enum Token {
     Comma,
     DoubleQuote,
     String(String),
-    Number(f64),
+    Number(Number),
     True,
     False,
     Null,
     Eof,
 }
 
-const LEFT_BRACE: char = '{';
-const RIGHT_BRACE: char = '}';
-const LEFT_BRACKET: char = '[';
-const RIGHT_BRACKET: char = ']';
-const COLLON: char = ':';
-const COMMA: char = ',';
-const DOUBLE_QUOTE: char = '"';
-const INITIAL_TRUE: char = 't';
-const INITIAL_FALSE: char = 'f';
-const INITIAL_NULL: char = 'n';
-const BACK_SLASH: char = '\\';
+const LEFT_BRACE: u8 = b'{';
+const RIGHT_BRACE: u8 = b'}';
+const LEFT_BRACKET: u8 = b'[';
+const RIGHT_BRACKET: u8 = b']';
+const COLLON: u8 = b':';
+const COMMA: u8 = b',';
+const DOUBLE_QUOTE: u8 = b'"';
+const INITIAL_TRUE: u8 = b't';
+const INITIAL_FALSE: u8 = b'f';
+const INITIAL_NULL: u8 = b'n';
+const BACK_SLASH: u8 = b'\\';
+
+/// Byte range of a token in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// `item` with the span of the source it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub item: T,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexerError {
+    pub message: String,
+    pub location: Option<Location>,
+}
+
+impl LexerError {
+    pub fn new(message: &str) -> LexerError {
+        LexerError {
+            message: message.to_string(),
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} at {}", self.message, location),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
 
+impl std::error::Error for LexerError {}
+
+/// Lexer scanning the input once. `position` is a byte offset, and tokens
+/// only start or end at ASCII bytes, so every slice of the input stays on a
+/// UTF-8 boundary.
 #[derive(Debug)]
 pub struct Lexer {
     input: String,
-    scope: Scope,
+    position: usize,
+    arbitrary_precision: bool,
 }
 
 impl Lexer {
     pub fn new(input: &str) -> Lexer {
         Lexer {
             input: input.to_string(),
-            scope: Scope::Global,
+            position: 0,
+            arbitrary_precision: false,
+        }
+    }
+
+    /// Keep the literal of every number so that it is written back exactly,
+    /// whatever its precision or range.
+    pub fn with_arbitrary_precision(mut self) -> Lexer {
+        self.arbitrary_precision = true;
+        self
+    }
+
+    /// Split the input into tokens with their spans.
+    pub fn run(&mut self) -> Result<Vec<Spanned<Token>>, LexerError> {
+        let mut tokens: Vec<Spanned<Token>> = Vec::new();
+        self.position = 0;
+        while let Some(&byte) = self.input.as_bytes().get(self.position) {
+            let start = self.position;
+            match byte {
+                LEFT_BRACE => self.push_symbol(&mut tokens, Token::LeftBrace),
+                RIGHT_BRACE => self.push_symbol(&mut tokens, Token::RightBrace),
+                LEFT_BRACKET => self.push_symbol(&mut tokens, Token::LeftBracket),
+                RIGHT_BRACKET => self.push_symbol(&mut tokens, Token::RightBracket),
+                COLLON => self.push_symbol(&mut tokens, Token::Colon),
+                COMMA => self.push_symbol(&mut tokens, Token::Comma),
+                INITIAL_TRUE => self.push_literal(&mut tokens, "true", Token::True)?,
+                INITIAL_FALSE => self.push_literal(&mut tokens, "false", Token::False)?,
+                INITIAL_NULL => self.push_literal(&mut tokens, "null", Token::Null)?,
+                b'-' | b'0'..=b'9' => {
+                    let number = self.read_number()?;
+                    self.push(&mut tokens, Token::Number(number), start);
+                }
+                DOUBLE_QUOTE => {
+                    let string = self.read_string()?;
+                    self.push(&mut tokens, Token::String(string), start);
+                }
+                b' ' | b'\t' | b'\n' | b'\r' => self.position += 1,
+                _ => {
+                    let c = self.input[self.position..].chars().next().unwrap();
+                    return Err(self.error(&format!("Unexpected character: {:?}", c)));
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Push `token` read from `start` up to the current position.
+    fn push(&self, tokens: &mut Vec<Spanned<Token>>, token: Token, start: usize) {
+        tokens.push(Spanned {
+            item: token,
+            span: Span {
+                start,
+                end: self.position,
+            },
+        });
+    }
+
+    /// Error located at the current position.
+    fn error(&self, message: &str) -> LexerError {
+        LexerError {
+            message: message.to_string(),
+            location: Some(Location::new(&self.input, self.position)),
+        }
+    }
+
+    fn push_symbol(&mut self, tokens: &mut Vec<Spanned<Token>>, token: Token) {
+        self.position += 1;
+        self.push(tokens, token, self.position - 1);
+    }
+
+    /// Push the token of `literal` when the input matches it exactly.
+    fn push_literal(
+        &mut self,
+        tokens: &mut Vec<Spanned<Token>>,
+        literal: &str,
+        token: Token,
+    ) -> Result<(), LexerError> {
+        if !self.input[self.position..].starts_with(literal) {
+            return Err(self.error(&format!("Invalid literal: expected {}", literal)));
         }
+        self.position += literal.len();
+        self.push(tokens, token, self.position - literal.len());
+        Ok(())
     }
 
-    pub fn run(&mut self) -> Vec<Token> {
-        let mut tokens: Vec<Token> = Vec::new();
-        let mut position: usize = 0;
-        while self.input.len() > position {
-            let char: char = self.input.chars().nth(position as usize).unwrap();
-            if self.scope != Scope::String {
-                if char == LEFT_BRACE {
-                    self.scope = Scope::Global;
-                    tokens.push(Token::LeftBrace);
-                    position += 1;
-                } else if char == RIGHT_BRACE {
-                    self.scope = Scope::Global;
-                    tokens.push(Token::RightBrace);
-                    position += 1;
-                } else if char == LEFT_BRACKET {
-                    self.scope = Scope::Global;
-                    tokens.push(Token::LeftBracket);
-                    position += 1;
-                } else if char == RIGHT_BRACKET {
-                    self.scope = Scope::Global;
-                    tokens.push(Token::RightBracket);
-                    position += 1;
-                } else if char == COLLON {
-                    self.scope = Scope::Global;
-                    tokens.push(Token::Colon);
-                    position += 1;
-                } else if char == COMMA {
-                    self.scope = Scope::Global;
-                    tokens.push(Token::Comma);
-                    position += 1;
-                } else if char == INITIAL_TRUE {
-                    self.scope = Scope::Global;
-                    position += 3;
-                    tokens.push(Token::True);
-                } else if char == INITIAL_FALSE {
-                    self.scope = Scope::Global;
-                    position += 4;
-                    tokens.push(Token::False);
-                } else if char == INITIAL_NULL {
-                    self.scope = Scope::Global;
-                    position += 3;
-                    tokens.push(Token::Null);
-                } else if char.to_string().parse::<f64>().is_ok() {
-                    self.scope = Scope::Global;
-                    let mut tmp_string: String = String::new();
-                    let mut tmp_char: char = char.clone();
-                    while tmp_char != COMMA && tmp_char != RIGHT_BRACE && tmp_char != RIGHT_BRACKET
-                    {
-                        tmp_string.push(tmp_char);
-                        position += 1;
-                        match self.input.chars().nth(position as usize) {
-                            Some(c) => tmp_char = c,
-                            None => break,
-                        }
-                    }
-                    let number = tmp_string.parse::<f64>().unwrap();
-                    tokens.push(Token::Number(number));
-                } else if char == DOUBLE_QUOTE {
-                    self.scope = Scope::String;
-                    position += 1;
-                } else {
-                    position += 1;
+    /// Read a number following the grammar of RFC 8259:
+    /// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`
+    fn read_number(&mut self) -> Result<Number, LexerError> {
+        let start = self.position;
+        self.skip_byte(b'-');
+        match self.peek() {
+            Some(b'0') => {
+                self.position += 1;
+                if matches!(self.peek(), Some(b'0'..=b'9')) {
+                    return Err(self.error("Invalid number: leading zeros are not allowed"));
                 }
-            } else {
-                if char == DOUBLE_QUOTE {
-                    self.scope = Scope::Global;
-                    position += 1;
-                } else {
-                    let mut tmp_string: String = String::new();
-                    let mut prev_char: char = ' ';
-                    let mut curr_char: char = char.clone();
-                    while curr_char != DOUBLE_QUOTE
-                        || (prev_char == BACK_SLASH && curr_char == DOUBLE_QUOTE)
-                    {
-                        tmp_string.push(curr_char);
-                        position += 1;
-                        prev_char = curr_char.clone();
-                        match self.input.chars().nth(position as usize) {
-                            Some(c) => curr_char = c,
-                            None => break,
-                        }
-                    }
-                    tokens.push(Token::String(tmp_string));
+            }
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return Err(self.error("Invalid number: expected a digit")),
+        }
+        if self.skip_byte(b'.') {
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("Invalid number: expected a digit after the decimal point"));
+            }
+            self.skip_digits();
+        }
+        if self.skip_byte(b'e') || self.skip_byte(b'E') {
+            if !self.skip_byte(b'+') {
+                self.skip_byte(b'-');
+            }
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("Invalid number: expected a digit in the exponent"));
+            }
+            self.skip_digits();
+        }
+        let number =
+            Number::from_literal(&self.input[start..self.position], self.arbitrary_precision);
+        if !self.arbitrary_precision && number.to_f64().is_infinite() {
+            return Err(self.error("Invalid number: out of range"));
+        }
+        Ok(number)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.position).copied()
+    }
+
+    /// Skip `byte` if it is the next one.
+    fn skip_byte(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_digits(&mut self) {
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.position += 1;
+        }
+    }
+
+    /// Read a string from its opening quote to its closing quote and decode
+    /// its escape sequences.
+    fn read_string(&mut self) -> Result<String, LexerError> {
+        let mut string = String::new();
+        self.position += 1;
+        let mut start = self.position;
+        loop {
+            let byte = match self.input.as_bytes().get(self.position) {
+                Some(&byte) => byte,
+                None => return Err(self.error("Unterminated string")),
+            };
+            match byte {
+                DOUBLE_QUOTE => {
+                    string.push_str(&self.input[start..self.position]);
+                    self.position += 1;
+                    return Ok(string);
+                }
+                BACK_SLASH => {
+                    string.push_str(&self.input[start..self.position]);
+                    self.position += 1;
+                    string.push(self.read_escape()?);
+                    start = self.position;
+                }
+                0x00..=0x1f => {
+                    return Err(self.error(&format!(
+                        "Unescaped control character in string: U+{:04X}",
+                        byte
+                    )))
                 }
+                _ => self.position += 1,
             }
         }
-        tokens
+    }
+
+    /// Decode the escape sequence following a backslash.
+    fn read_escape(&mut self) -> Result<char, LexerError> {
+        let byte = match self.input.as_bytes().get(self.position) {
+            Some(&byte) => byte,
+            None => return Err(self.error("Unterminated string")),
+        };
+        self.position += 1;
+        let escaped = match byte {
+            DOUBLE_QUOTE => '"',
+            BACK_SLASH => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => return self.read_unicode_escape(),
+            _ => {
+                let c = self.input[self.position - 1..]
+                    .chars()
+                    .next()
+                    .unwrap_or('?');
+                return Err(self.error(&format!("Invalid escape: \\{}", c)));
+            }
+        };
+        Ok(escaped)
+    }
+
+    /// Decode `XXXX` of `\uXXXX`, combining a surrogate pair into one char.
+    fn read_unicode_escape(&mut self) -> Result<char, LexerError> {
+        let high = self.read_hex4()?;
+        let code = match high {
+            0xd800..=0xdbff => {
+                if !self.input[self.position..].starts_with("\\u") {
+                    return Err(self.error(&format!("Lone surrogate: \\u{:04x}", high)));
+                }
+                self.position += 2;
+                let low = self.read_hex4()?;
+                if !(0xdc00..=0xdfff).contains(&low) {
+                    return Err(self.error(&format!("Lone surrogate: \\u{:04x}", high)));
+                }
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+            }
+            0xdc00..=0xdfff => return Err(self.error(&format!("Lone surrogate: \\u{:04x}", high))),
+            _ => high,
+        };
+        Ok(char::from_u32(code).unwrap())
+    }
+
+    fn read_hex4(&mut self) -> Result<u32, LexerError> {
+        let digits = self
+            .input
+            .get(self.position..self.position + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("Invalid unicode escape: expected 4 hex digits"))?;
+        self.position += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap())
     }
 }
 
@@ -141,10 +326,15 @@ impl Lexer {
 mod tests {
     use super::*;
 
+    fn tokens(json: &str) -> Result<Vec<Token>, LexerError> {
+        let tokens = Lexer::new(json).run()?;
+        Ok(tokens.into_iter().map(|token| token.item).collect())
+    }
+
     #[test]
     fn empty_object() {
         let json = r#"{     }"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![Token::LeftBrace, Token::RightBrace];
         assert!(ret.eq(&expected));
     }
@@ -153,7 +343,7 @@ mod tests {
     fn blank_object() {
         let json = r#"{
         }"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![Token::LeftBrace, Token::RightBrace];
         assert!(ret.eq(&expected));
     }
@@ -161,7 +351,7 @@ mod tests {
     #[test]
     fn empty_array() {
         let json = r#"[]"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![Token::LeftBracket, Token::RightBracket];
         assert!(ret.eq(&expected));
     }
@@ -169,12 +359,12 @@ mod tests {
     #[test]
     fn filled_array() {
         let json = r#"[1,2]"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![
             Token::LeftBracket,
-            Token::Number(1.0),
+            Token::Number(Number::from(1u64)),
             Token::Comma,
-            Token::Number(2.0),
+            Token::Number(Number::from(2u64)),
             Token::RightBracket,
         ];
         assert!(ret.eq(&expected));
@@ -183,40 +373,41 @@ mod tests {
     #[test]
     fn number_value() {
         let json = r#"1"#;
-        let ret = Lexer::new(json).run();
-        let expected = vec![Token::Number(1.0)];
+        let ret = tokens(json).unwrap();
+        let expected = vec![Token::Number(Number::from(1u64))];
         assert!(ret.eq(&expected));
     }
 
     #[test]
     fn numbers_value() {
         let json = r#"123"#;
-        let ret = Lexer::new(json).run();
-        let expected = vec![Token::Number(123.0)];
+        let ret = tokens(json).unwrap();
+        let expected = vec![Token::Number(Number::from(123u64))];
         assert!(ret.eq(&expected));
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn float_number_value() {
         let json = r#"3.14"#;
-        let ret = Lexer::new(json).run();
-        let expected = vec![Token::Number(3.14)];
+        let ret = tokens(json).unwrap();
+        let expected = vec![Token::Number(Number::from(3.14))];
         assert!(ret.eq(&expected));
     }
 
     #[test]
     fn binint_number_value() {
         let json = r#"6.62607e-34"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         println!("{:?}", ret);
-        let expected = vec![Token::Number(6.62607e-34)];
+        let expected = vec![Token::Number(Number::from(6.62607e-34))];
         assert!(ret.eq(&expected));
     }
 
     #[test]
     fn true_value() {
         let json = r#"true"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![Token::True];
         assert!(ret.eq(&expected));
     }
@@ -224,7 +415,7 @@ mod tests {
     #[test]
     fn false_value() {
         let json = r#"false"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![Token::False];
         assert!(ret.eq(&expected));
     }
@@ -232,7 +423,7 @@ mod tests {
     #[test]
     fn null_value() {
         let json = r#"null"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![Token::Null];
         assert!(ret.eq(&expected));
     }
@@ -240,7 +431,7 @@ mod tests {
     #[test]
     fn string_value() {
         let json = r#""abc""#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![Token::String(String::from("abc"))];
         assert!(ret.eq(&expected));
     }
@@ -257,7 +448,7 @@ mod tests {
           "y": ["a", "b"],
           "z": {"a": 1, "b": {"c": 2.5}},
         }"#;
-        let ret = Lexer::new(json).run();
+        let ret = tokens(json).unwrap();
         let expected = vec![
             Token::LeftBrace,
             Token::String(String::from("name")),
@@ -266,7 +457,7 @@ mod tests {
             Token::Comma,
             Token::String(String::from("age")),
             Token::Colon,
-            Token::Number(32.0),
+            Token::Number(Number::from(32u64)),
             Token::Comma,
             Token::String(String::from("is_programmer")),
             Token::Colon,
@@ -283,11 +474,11 @@ mod tests {
             Token::String(String::from("x")),
             Token::Colon,
             Token::LeftBracket,
-            Token::Number(1.2),
+            Token::Number(Number::from(1.2)),
             Token::Comma,
-            Token::Number(2.0),
+            Token::Number(Number::from(2u64)),
             Token::Comma,
-            Token::Number(3.5),
+            Token::Number(Number::from(3.5)),
             Token::RightBracket,
             Token::Comma,
             Token::String(String::from("y")),
@@ -303,14 +494,14 @@ mod tests {
             Token::LeftBrace,
             Token::String(String::from("a")),
             Token::Colon,
-            Token::Number(1.0),
+            Token::Number(Number::from(1u64)),
             Token::Comma,
             Token::String(String::from("b")),
             Token::Colon,
             Token::LeftBrace,
             Token::String(String::from("c")),
             Token::Colon,
-            Token::Number(2.5),
+            Token::Number(Number::from(2.5)),
             Token::RightBrace,
             Token::RightBrace,
             Token::Comma,
@@ -319,11 +510,214 @@ mod tests {
         assert!(ret.eq(&expected));
     }
 
-    // TODO: escaped string is not supported yet
-    // #[test]
-    // fn escaped_string_value() {
-    //     let json = r#""ab\"c""#;
-    //     let ret = Lexer::new(json).run();
-    //     assert_eq!(ret, vec!["\"", "ab\"c", "\""]);
-    // }
+    #[test]
+    fn multibyte_string_value() {
+        let json = r#"{"名前": "中坂 雄平", "emoji": "🦀"}"#;
+        let ret = tokens(json).unwrap();
+        let expected = vec![
+            Token::LeftBrace,
+            Token::String(String::from("名前")),
+            Token::Colon,
+            Token::String(String::from("中坂 雄平")),
+            Token::Comma,
+            Token::String(String::from("emoji")),
+            Token::Colon,
+            Token::String(String::from("🦀")),
+            Token::RightBrace,
+        ];
+        assert_eq!(ret, expected);
+    }
+
+    #[test]
+    fn large_input() {
+        // Rescanning the input for every character would take minutes here.
+        let item = r#"{"key": "値", "n": 12.5, "ok": true},"#;
+        let json = format!("[{}null]", item.repeat(50_000));
+        let ret = tokens(&json).unwrap();
+        assert_eq!(ret.len(), 50_000 * 14 + 3);
+        assert_eq!(ret[4], Token::String(String::from("値")));
+    }
+
+    #[test]
+    fn escaped_string_value() {
+        let json = r#""ab\"c\\d\/e\b\f\n\r\t""#;
+        let ret = tokens(json).unwrap();
+        assert_eq!(
+            ret,
+            vec![Token::String(String::from("ab\"c\\d/e\u{8}\u{c}\n\r\t"))]
+        );
+
+        let json = r#"["\\", "x"]"#;
+        let ret = tokens(json).unwrap();
+        assert_eq!(
+            ret,
+            vec![
+                Token::LeftBracket,
+                Token::String(String::from("\\")),
+                Token::Comma,
+                Token::String(String::from("x")),
+                Token::RightBracket,
+            ]
+        );
+    }
+
+    #[test]
+    fn unicode_escape() {
+        let json = r#""\u00e9\u4E2D \ud83e\udd80""#;
+        let ret = tokens(json).unwrap();
+        assert_eq!(ret, vec![Token::String(String::from("é中 🦀"))]);
+    }
+
+    #[test]
+    fn invalid_strings() {
+        let cases = [
+            (r#""abc"#, "Unterminated string"),
+            (r#""a\x""#, "Invalid escape: \\x"),
+            (r#""\u12""#, "Invalid unicode escape: expected 4 hex digits"),
+            (r#""\ud83e""#, "Lone surrogate: \\ud83e"),
+            (r#""\ud83e\u0041""#, "Lone surrogate: \\ud83e"),
+            (r#""\udd80""#, "Lone surrogate: \\udd80"),
+            ("\"a\tb\"", "Unescaped control character in string: U+0009"),
+        ];
+        for (json, message) in cases {
+            assert_eq!(
+                tokens(json).map_err(|e| e.message),
+                Err(message.to_string()),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_literals() {
+        let cases = [
+            ("tru", "Invalid literal: expected true"),
+            ("[nope]", "Invalid literal: expected null"),
+            ("fals", "Invalid literal: expected false"),
+            ("truex", "Unexpected character: 'x'"),
+            ("[true, True]", "Unexpected character: 'T'"),
+            ("{\"a\": 1} #", "Unexpected character: '#'"),
+            ("[あ]", "Unexpected character: 'あ'"),
+        ];
+        for (json, message) in cases {
+            assert_eq!(
+                tokens(json).map_err(|e| e.message),
+                Err(message.to_string()),
+                "{}",
+                json
+            );
+        }
+        assert_eq!(
+            tokens("[true,false,null]"),
+            Ok(vec![
+                Token::LeftBracket,
+                Token::True,
+                Token::Comma,
+                Token::False,
+                Token::Comma,
+                Token::Null,
+                Token::RightBracket,
+            ])
+        );
+    }
+
+    #[test]
+    fn number_grammar() {
+        let cases = [
+            ("0", Number::from(0u64)),
+            ("-0", Number::from(0u64)),
+            ("-5", Number::from(-5i64)),
+            ("10.25", Number::from(10.25)),
+            ("-1.5e3", Number::from(-1500.0)),
+            ("2E+2", Number::from(200.0)),
+            ("2e-2", Number::from(0.02)),
+            ("1 ", Number::from(1u64)),
+        ];
+        for (json, number) in cases {
+            assert_eq!(tokens(json), Ok(vec![Token::Number(number)]), "{}", json);
+        }
+        assert_eq!(
+            tokens("[-1 , 2]"),
+            Ok(vec![
+                Token::LeftBracket,
+                Token::Number(Number::from(-1i64)),
+                Token::Comma,
+                Token::Number(Number::from(2u64)),
+                Token::RightBracket,
+            ])
+        );
+    }
+
+    #[test]
+    fn arbitrary_precision_numbers() {
+        let json = "[12345678901234567890123, 1.50, 1e999]";
+        let ret = Lexer::new(json).with_arbitrary_precision().run().unwrap();
+        let literals: Vec<String> = ret
+            .iter()
+            .filter_map(|token| match &token.item {
+                Token::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(literals, vec!["12345678901234567890123", "1.50", "1e999"]);
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        let cases = [
+            ("01", "Invalid number: leading zeros are not allowed"),
+            ("-", "Invalid number: expected a digit"),
+            ("-a", "Invalid number: expected a digit"),
+            (
+                "1.",
+                "Invalid number: expected a digit after the decimal point",
+            ),
+            (
+                "1.e5",
+                "Invalid number: expected a digit after the decimal point",
+            ),
+            ("1e", "Invalid number: expected a digit in the exponent"),
+            ("1e+", "Invalid number: expected a digit in the exponent"),
+            ("1e999", "Invalid number: out of range"),
+            ("+1", "Unexpected character: '+'"),
+            (".5", "Unexpected character: '.'"),
+            ("1x", "Unexpected character: 'x'"),
+        ];
+        for (json, message) in cases {
+            assert_eq!(
+                tokens(json).map_err(|e| e.message),
+                Err(message.to_string()),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn spans() {
+        let json = "{\"名\": [1.5, true]}";
+        let spans: Vec<&str> = Lexer::new(json)
+            .run()
+            .unwrap()
+            .iter()
+            .map(|token| &json[token.span.start..token.span.end])
+            .collect();
+        assert_eq!(
+            spans,
+            vec!["{", "\"名\"", ":", "[", "1.5", ",", "true", "]", "}"]
+        );
+    }
+
+    #[test]
+    fn error_location() {
+        let json = "{\n  \"a\": [1, 2],\n  \"b\": nul\n}";
+        let error = Lexer::new(json).run().unwrap_err();
+        let location = error.location.clone().unwrap();
+        assert_eq!((location.line, location.column), (3, 8));
+        assert_eq!(
+            error.to_string(),
+            "Invalid literal: expected null at line 3, column 8\n  \"b\": nul\n       ^"
+        );
+    }
 }
@@ -1,14 +1,160 @@
-use std::collections::BTreeMap;
+use std::io;
 
+pub mod canonical;
+pub mod error;
 pub mod lexer;
+pub mod map;
+pub mod number;
 pub mod parser;
+pub mod serializer;
+
+pub use error::Error;
+pub use map::Map;
+pub use number::Number;
+pub use serializer::{NonFinite, SerializeError, Serializer};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     String(String),
-    Number(f64),
+    Number(Number),
     Boolean(bool),
     Null,
-    Object(BTreeMap<String, Value>),
+    Object(Map),
     Array(Vec<Value>),
 }
+
+/// Parse a JSON document strictly: trailing commas and anything but
+/// whitespace after the root value are errors.
+pub fn from_str(s: &str) -> Result<Value, Error> {
+    let tokens = lexer::Lexer::new(s).run()?;
+    Ok(parser::Parser::new(tokens).with_source(s).parse()?)
+}
+
+/// Parse a JSON document accepting trailing commas and ignoring what follows
+/// the root value.
+pub fn from_str_lenient(s: &str) -> Result<Value, Error> {
+    let tokens = lexer::Lexer::new(s).run()?;
+    Ok(parser::Parser::new(tokens)
+        .with_source(s)
+        .lenient()
+        .parse()?)
+}
+
+/// Parse a JSON document strictly, keeping the literal of every number so
+/// that none loses precision or overflows.
+pub fn from_str_arbitrary_precision(s: &str) -> Result<Value, Error> {
+    let tokens = lexer::Lexer::new(s).with_arbitrary_precision().run()?;
+    Ok(parser::Parser::new(tokens).with_source(s).parse()?)
+}
+
+/// Write `value` as compact JSON.
+pub fn to_string(value: &Value) -> Result<String, SerializeError> {
+    Serializer::new().to_string(value)
+}
+
+/// Write `value` as JSON indented by two spaces per level.
+pub fn to_string_pretty(value: &Value) -> Result<String, SerializeError> {
+    Serializer::pretty().to_string(value)
+}
+
+pub fn to_writer<W: io::Write>(writer: &mut W, value: &Value) -> Result<(), SerializeError> {
+    Serializer::new().write(writer, value)
+}
+
+pub fn to_writer_pretty<W: io::Write>(writer: &mut W, value: &Value) -> Result<(), SerializeError> {
+    Serializer::pretty().write(writer, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::ParserErrorKind;
+
+    fn kind(result: Result<Value, Error>) -> Option<ParserErrorKind> {
+        match result {
+            Err(Error::Parser(e)) => Some(e.kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn strict() {
+        let value = from_str(" {\"a\": [1, true]}\n").unwrap();
+        let mut object = Map::new();
+        object.insert(
+            "a".to_string(),
+            Value::Array(vec![
+                Value::Number(Number::from(1u64)),
+                Value::Boolean(true),
+            ]),
+        );
+        assert_eq!(value, Value::Object(object));
+
+        assert_eq!(
+            kind(from_str("[1, 2,]")),
+            Some(ParserErrorKind::TrailingComma)
+        );
+        assert_eq!(
+            kind(from_str(r#"{"a": 1,}"#)),
+            Some(ParserErrorKind::TrailingComma)
+        );
+        assert_eq!(
+            kind(from_str("[1] [2]")),
+            Some(ParserErrorKind::TrailingContent)
+        );
+        assert_eq!(
+            kind(from_str("1 2")),
+            Some(ParserErrorKind::TrailingContent)
+        );
+        assert!(matches!(from_str("[1] x"), Err(Error::Lexer(_))));
+
+        let error = from_str("{\n  \"a\": 1,\n}").unwrap_err();
+        assert_eq!(error.message(), "Trailing comma");
+        assert_eq!(error.location().map(|l| (l.line, l.column)), Some((2, 9)));
+    }
+
+    #[test]
+    fn lenient() {
+        let json = r#"{
+          "x": [1.2, 2, 3.5,],
+          "z": {"a": 1, "b": {"c": 2.5}},
+        } [1, "trailing"]"#;
+        assert_eq!(kind(from_str(json)), Some(ParserErrorKind::TrailingComma));
+        let value = from_str_lenient(json).unwrap();
+        assert_eq!(
+            value,
+            from_str(r#"{"x": [1.2, 2, 3.5], "z": {"a": 1, "b": {"c": 2.5}}}"#).unwrap()
+        );
+        assert!(from_str_lenient("[,]").is_err());
+    }
+
+    #[test]
+    fn arbitrary_precision() {
+        let json = "[12345678901234567890123, 1.50, 1e999]";
+        assert!(from_str(json).is_err());
+        let value = from_str_arbitrary_precision(json).unwrap();
+        assert_eq!(
+            to_string(&value).unwrap(),
+            "[12345678901234567890123,1.50,1e999]"
+        );
+        assert_eq!(
+            kind(from_str_arbitrary_precision("[1,]")),
+            Some(ParserErrorKind::TrailingComma)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let json = r#"{"a": [1, -2, 0.5, 1e300, "x\ny"], "b": {"c": null, "d": false}}"#;
+        let value = from_str(json).unwrap();
+        assert_eq!(from_str(&to_string(&value).unwrap()).unwrap(), value);
+        assert_eq!(from_str(&to_string_pretty(&value).unwrap()).unwrap(), value);
+
+        let mut out = vec![];
+        to_writer(&mut out, &value).unwrap();
+        assert_eq!(out, to_string(&value).unwrap().into_bytes());
+        out.clear();
+        to_writer_pretty(&mut out, &value).unwrap();
+        assert_eq!(out, to_string_pretty(&value).unwrap().into_bytes());
+    }
+}
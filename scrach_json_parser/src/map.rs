@@ -0,0 +1,160 @@
+use crate::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Index;
+
+/// Members of a JSON object in the order they were inserted. Equality ignores
+/// the order, as JSON objects are unordered.
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, Value)>,
+    index: BTreeMap<String, usize>,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    /// Set `key` to `value` and return the previous value. A new key goes
+    /// last; an existing one keeps its place.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        if let Some(&i) = self.index.get(&key) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        None
+    }
+
+    /// Remove `key`, shifting the members after it.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for position in self.index.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        let i = *self.index.get(key)?;
+        Some(&mut self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Members in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Members sorted by key bytes.
+    pub fn sorted(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.index.iter().map(|(key, &i)| (key, &self.entries[i].1))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Index<&str> for Map {
+    type Output = Value;
+
+    /// Panics when `key` is missing, as `BTreeMap` does.
+    fn index(&self, key: &str) -> &Value {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl FromIterator<(String, Value)> for Map {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Map {
+        let mut map = Map::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insertion_order() {
+        let mut map: Map = [("b", 1u64), ("a", 2), ("c", 3)]
+            .into_iter()
+            .map(|(key, n)| (key.to_string(), Value::Number(n.into())))
+            .collect();
+        assert_eq!(
+            map.insert("b".to_string(), Value::Null),
+            Some(Value::Number(1u64.into()))
+        );
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(
+            map.sorted().map(|(key, _)| key).collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+
+        assert_eq!(map.remove("a"), Some(Value::Number(2u64.into())));
+        assert_eq!(map.remove("a"), None);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "c"]);
+        assert_eq!(map.get("c"), Some(&Value::Number(3u64.into())));
+        *map.get_mut("c").unwrap() = Value::Boolean(true);
+        assert_eq!(map.get("c"), Some(&Value::Boolean(true)));
+        assert_eq!(map["b"], Value::Null);
+        assert!(!map.contains_key("a") && map.len() == 2);
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let a: Map = [("x", Value::Null), ("y", Value::Boolean(true))]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        let b: Map = [("y", Value::Boolean(true)), ("x", Value::Null)]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        assert_eq!(b.keys().collect::<Vec<_>>(), ["y", "x"]);
+        assert_eq!(a, b);
+        assert_ne!(a, Map::new());
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum N {
+    PosInt(u64),
+    /// Always below zero.
+    NegInt(i64),
+    Float(f64),
+}
+
+/// JSON number kept without loss. Integers are stored as `u64` or `i64` and
+/// other numbers as `f64`. In arbitrary precision mode the original literal
+/// is kept as well and written back as it is. Numbers are equal when their
+/// values are, whatever their literals: `1.50` equals `1.5` but not `1`.
+#[derive(Debug, Clone)]
+pub struct Number {
+    n: N,
+    text: Option<String>,
+}
+
+/// Largest integer whose every smaller integer an `f64` holds exactly.
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+impl Number {
+    /// Build a number from a literal already checked against the grammar.
+    pub(crate) fn from_literal(text: &str, keep_text: bool) -> Number {
+        let is_integer = !text.contains(['.', 'e', 'E']);
+        let n = if is_integer && text.starts_with('-') {
+            match text.parse::<i64>() {
+                Ok(0) => N::PosInt(0),
+                Ok(i) => N::NegInt(i),
+                Err(_) => N::Float(text.parse().unwrap()),
+            }
+        } else if is_integer {
+            match text.parse::<u64>() {
+                Ok(u) => N::PosInt(u),
+                Err(_) => N::Float(text.parse().unwrap()),
+            }
+        } else {
+            N::Float(text.parse().unwrap())
+        };
+        Number {
+            n,
+            text: if keep_text {
+                Some(text.to_string())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Original literal, kept in arbitrary precision mode.
+    pub fn as_str(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        !matches!(self.n, N::Float(_))
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self.n, N::Float(_))
+    }
+
+    /// Return the number when it is an integer in the range of `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(u) => i64::try_from(u).ok(),
+            N::NegInt(i) => Some(i),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Return the number when it is a non-negative integer.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::PosInt(u) => Some(u),
+            _ => None,
+        }
+    }
+
+    /// Return the number when an `f64` holds it exactly: finite floats and
+    /// integers up to 2^53 in magnitude.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.n {
+            N::PosInt(u) if u <= MAX_EXACT_INTEGER => Some(u as f64),
+            N::NegInt(i) if i.unsigned_abs() <= MAX_EXACT_INTEGER => Some(i as f64),
+            N::Float(f) if f.is_finite() => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Return the nearest `f64`, losing precision if needed.
+    pub fn to_f64(&self) -> f64 {
+        match self.n {
+            N::PosInt(u) => u as f64,
+            N::NegInt(i) => i as f64,
+            N::Float(f) => f,
+        }
+    }
+
+    /// Exact value, `None` for NaN and infinities.
+    fn decimal(&self) -> Option<Decimal> {
+        match (&self.text, self.n) {
+            (Some(text), _) => Decimal::parse(text),
+            (None, N::PosInt(u)) => Decimal::parse(&u.to_string()),
+            (None, N::NegInt(i)) => Decimal::parse(&i.to_string()),
+            // The shortest digits that read back as `f`.
+            (None, N::Float(f)) if f.is_finite() => Decimal::parse(&format!("{:e}", f)),
+            (None, N::Float(_)) => None,
+        }
+    }
+}
+
+impl PartialEq for Number {
+    /// Compare exact values: the literal when it is kept, or else the stored
+    /// value. Integers never equal floats, infinities only equal themselves
+    /// and NaN equals nothing.
+    fn eq(&self, other: &Number) -> bool {
+        match (self.decimal(), other.decimal()) {
+            (Some(a), Some(b)) => self.is_integer() == other.is_integer() && a == b,
+            (None, None) => self.n == other.n,
+            _ => false,
+        }
+    }
+}
+
+/// Exact value of a literal as `digits * 10^exponent`, with neither leading
+/// nor trailing zeros in `digits`. Zero has no digits nor sign.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Decimal {
+    negative: bool,
+    digits: String,
+    exponent: i64,
+}
+
+impl Decimal {
+    /// Return `None` when the exponent is out of the range of `i64`.
+    pub(crate) fn parse(text: &str) -> Option<Decimal> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(i) => (&text[..i], text[i + 1..].parse::<i64>().ok()?),
+            None => (text, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{}{}", integer, fraction);
+        let digits = digits.trim_start_matches('0');
+        let trimmed = digits.trim_end_matches('0');
+        let exponent = exponent
+            .checked_sub(fraction.len() as i64)?
+            .checked_add((digits.len() - trimmed.len()) as i64)?;
+        Some(Decimal {
+            negative: negative && !trimmed.is_empty(),
+            digits: trimmed.to_string(),
+            exponent: if trimmed.is_empty() { 0 } else { exponent },
+        })
+    }
+}
+
+impl From<u64> for Number {
+    fn from(u: u64) -> Number {
+        Number {
+            n: N::PosInt(u),
+            text: None,
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(i: i64) -> Number {
+        let n = if i < 0 {
+            N::NegInt(i)
+        } else {
+            N::PosInt(i as u64)
+        };
+        Number { n, text: None }
+    }
+}
+
+impl From<f64> for Number {
+    fn from(f: f64) -> Number {
+        Number {
+            n: N::Float(f),
+            text: None,
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    /// Floats always show a fraction or an exponent, so `1.0` stays a float.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(text) = &self.text {
+            return f.write_str(text);
+        }
+        match self.n {
+            N::PosInt(u) => write!(f, "{}", u),
+            N::NegInt(i) => write!(f, "{}", i),
+            N::Float(x) => write!(f, "{:?}", x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers() {
+        let big = Number::from_literal("18446744073709551615", false);
+        assert_eq!(big.as_u64(), Some(u64::MAX));
+        assert_eq!(big.as_i64(), None);
+        assert_eq!(big.as_f64(), None);
+
+        let id = Number::from_literal("9007199254740993", false);
+        assert_eq!(id.as_u64(), Some(9_007_199_254_740_993));
+        assert_eq!(id.to_string(), "9007199254740993");
+
+        let negative = Number::from_literal("-9223372036854775808", false);
+        assert_eq!(negative.as_i64(), Some(i64::MIN));
+        assert_eq!(negative.as_u64(), None);
+
+        assert_eq!(Number::from_literal("3", false).as_f64(), Some(3.0));
+    }
+
+    #[test]
+    fn integer_or_float() {
+        let one = Number::from_literal("1", false);
+        let float_one = Number::from_literal("1.0", false);
+        assert_ne!(one, float_one);
+        assert!(one.is_integer() && float_one.is_f64());
+        assert_eq!(float_one.as_i64(), None);
+        assert_eq!(one.to_string(), "1");
+        assert_eq!(float_one.to_string(), "1.0");
+        let zero = Number::from_literal("-0", false);
+        assert_eq!(zero.to_string(), "0");
+        assert_eq!(zero.as_i64(), Some(0));
+        assert_eq!(zero, Number::from(0u64));
+        assert_eq!(Number::from_literal("1e2", false).as_f64(), Some(100.0));
+    }
+
+    #[test]
+    fn arbitrary_precision() {
+        for literal in [
+            "123456789012345678901234567890",
+            "0.1000000000000000000000001",
+            "1E+2",
+            "-0",
+            "1e999",
+        ] {
+            let number = Number::from_literal(literal, true);
+            assert_eq!(number.as_str(), Some(literal));
+            assert_eq!(number.to_string(), literal);
+        }
+        assert_eq!(Number::from_literal("1e999", true).as_f64(), None);
+    }
+
+    #[test]
+    fn equal_values() {
+        let number = |literal| Number::from_literal(literal, true);
+        assert_eq!(number("1.50"), number("1.5"));
+        assert_eq!(number("1.5"), Number::from_literal("1.5", false));
+        assert_eq!(number("1E+2"), number("100.0"));
+        assert_eq!(number("0.0"), number("-0e5"));
+        assert_eq!(number("-12.3e-1"), number("-1.23"));
+        assert_ne!(number("1"), number("1.0"));
+        assert_ne!(number("1.5"), number("-1.5"));
+        // Both round to the same `f64`.
+        assert_ne!(
+            number("123456789012345678901234567890"),
+            number("123456789012345678901234567891")
+        );
+        assert_ne!(number("1e999"), number("1e998"));
+        assert_ne!(number("1e999"), Number::from(f64::INFINITY));
+    }
+
+    #[test]
+    fn equality_is_transitive() {
+        let rounded = Number::from_literal("123456789012345678901234567890", false);
+        let a = Number::from_literal("123456789012345678901234567890", true);
+        let b = Number::from_literal("123456789012345678901234567891", true);
+        assert_ne!(a, b);
+        assert_ne!(rounded, a);
+        assert_ne!(rounded, b);
+        assert_eq!(rounded, Number::from_literal("1.2345678901234568e29", true));
+        assert_eq!(Number::from(0.1), Number::from_literal("0.10", true));
+        assert_eq!(Number::from(-7i64), Number::from_literal("-7", true));
+    }
+}
@@ -1,120 +1,259 @@
-use crate::lexer::Token;
-use crate::Value;
-use std::collections::BTreeMap;
+use crate::error::Location;
+use crate::lexer::{Spanned, Token};
+use crate::{Map, Value};
+use std::fmt;
+
+/// Nesting depth of arrays and objects beyond which parsing stops, so that
+/// deep inputs cannot overflow the stack.
+pub const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParserErrorKind {
+    UnexpectedEof,
+    UnexpectedToken,
+    ExpectedKey,
+    ExpectedColon,
+    ExpectedCommaOrRightBrace,
+    ExpectedCommaOrRightBracket,
+    TrailingComma,
+    TrailingContent,
+    TooDeep,
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParserError {
+    pub kind: ParserErrorKind,
     pub message: String,
+    pub location: Option<Location>,
 }
 
 impl ParserError {
-    pub fn new(message: &str) -> ParserError {
+    pub fn new(kind: ParserErrorKind, message: &str) -> ParserError {
         ParserError {
+            kind,
             message: message.to_string(),
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} at {}", self.message, location),
+            None => write!(f, "{}", self.message),
         }
     }
 }
 
+impl std::error::Error for ParserError {}
+
 pub struct Parser {
-    tokens: Vec<Token>,
+    tokens: Vec<Spanned<Token>>,
+    source: Option<String>,
     position: usize,
+    depth: usize,
+    strict: bool,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Parser {
+    pub fn new(tokens: Vec<Spanned<Token>>) -> Parser {
         Parser {
             tokens,
+            source: None,
             position: 0,
+            depth: 0,
+            strict: true,
         }
     }
 
-    pub fn parse(&mut self) -> Result<Value, ParserError> {
-        let token = self.tokens.get(self.position).unwrap().clone();
-        let result = match token {
-            Token::LeftBrace => self.parse_object(),
-            Token::LeftBracket => self.parse_array(),
-            Token::String(s) => {
-                self.position += 1;
-                Ok(Value::String(s.clone()))
-            }
-            Token::Number(s) => {
-                self.position += 1;
-                Ok(Value::Number(s.clone()))
-            }
-            Token::True => {
-                self.position += 1;
-                Ok(Value::Boolean(true))
-            }
-            Token::False => {
+    /// Locate errors in `source`, the input the tokens were read from.
+    pub fn with_source(mut self, source: &str) -> Parser {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Accept trailing commas in arrays and objects and ignore anything after
+    /// the root value.
+    pub fn lenient(mut self) -> Parser {
+        self.strict = false;
+        self
+    }
+
+    /// Error located at the token at `index`, or at the end of the source
+    /// past the last token.
+    fn error(&self, index: usize, kind: ParserErrorKind, message: &str) -> ParserError {
+        ParserError {
+            kind,
+            message: message.to_string(),
+            location: self.source.as_ref().map(|source| {
+                let offset = self
+                    .tokens
+                    .get(index)
+                    .map_or(source.len(), |token| token.span.start);
+                Location::new(source, offset)
+            }),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|token| &token.item)
+    }
+
+    /// Take the next token. Running out of tokens is an error.
+    fn next_token(&mut self) -> Result<Token, ParserError> {
+        match self.peek() {
+            Some(Token::Eof) | None => Err(self.error(
+                self.position,
+                ParserErrorKind::UnexpectedEof,
+                "Unexpected end of input",
+            )),
+            Some(token) => {
+                let token = token.clone();
                 self.position += 1;
-                Ok(Value::Boolean(false))
+                Ok(token)
             }
-            Token::Null => {
-                self.position += 1;
-                Ok(Value::Null)
+        }
+    }
+
+    /// Whether the comma just taken is followed by `close`. Such a trailing
+    /// comma is an error in strict mode.
+    fn trailing_comma(&mut self, close: &Token) -> Result<bool, ParserError> {
+        if self.peek() != Some(close) {
+            return Ok(false);
+        }
+        if self.strict {
+            return Err(self.error(
+                self.position - 1,
+                ParserErrorKind::TrailingComma,
+                "Trailing comma",
+            ));
+        }
+        self.position += 1;
+        Ok(true)
+    }
+
+    /// Take the next token and check it with `expected`, failing with `kind`.
+    fn expect(
+        &mut self,
+        expected: impl Fn(&Token) -> bool,
+        kind: ParserErrorKind,
+        message: &str,
+    ) -> Result<Token, ParserError> {
+        let token = self.next_token()?;
+        if expected(&token) {
+            Ok(token)
+        } else {
+            Err(self.error(self.position - 1, kind, message))
+        }
+    }
+
+    /// Parse the root value. In strict mode nothing may follow it.
+    pub fn parse(&mut self) -> Result<Value, ParserError> {
+        let value = self.parse_value()?;
+        if self.strict {
+            if let Some(token) = self.peek() {
+                if token != &Token::Eof {
+                    return Err(self.error(
+                        self.position,
+                        ParserErrorKind::TrailingContent,
+                        "Unexpected content after the root value",
+                    ));
+                }
             }
-            _ => Err(ParserError::new(&format!(
-                "Unacceptable token: {:?}",
-                token
-            ))),
-        };
-        result
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, ParserError> {
+        match self.next_token()? {
+            Token::LeftBrace => self.nested(Parser::parse_object),
+            Token::LeftBracket => self.nested(Parser::parse_array),
+            Token::String(s) => Ok(Value::String(s)),
+            Token::Number(n) => Ok(Value::Number(n)),
+            Token::True => Ok(Value::Boolean(true)),
+            Token::False => Ok(Value::Boolean(false)),
+            Token::Null => Ok(Value::Null),
+            token => Err(self.error(
+                self.position - 1,
+                ParserErrorKind::UnexpectedToken,
+                &format!("Unacceptable token: {:?}", token),
+            )),
+        }
+    }
+
+    /// Parse the content of an array or an object one level deeper.
+    fn nested(
+        &mut self,
+        parse: fn(&mut Parser) -> Result<Value, ParserError>,
+    ) -> Result<Value, ParserError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(
+                self.position - 1,
+                ParserErrorKind::TooDeep,
+                &format!("Nesting deeper than {} levels", MAX_DEPTH),
+            ));
+        }
+        self.depth += 1;
+        let ret = parse(self);
+        self.depth -= 1;
+        ret
     }
 
     fn parse_object(&mut self) -> Result<Value, ParserError> {
-        self.position += 1;
-        let mut object = BTreeMap::new();
-        if self.tokens.get(self.position).unwrap() == &Token::RightBrace {
+        let mut object = Map::new();
+        if self.peek() == Some(&Token::RightBrace) {
+            self.position += 1;
             return Ok(Value::Object(object));
         }
 
         loop {
-            let key_token = self.tokens.get(self.position).unwrap().clone();
-            self.position += 1;
-            let colon_token = self.tokens.get(self.position).unwrap().clone();
-            self.position += 1;
-
-            match (key_token, colon_token) {
-                (Token::String(key), Token::Colon) => {
-                    object.insert(key.to_string(), self.parse().unwrap());
+            let key = match self.next_token()? {
+                Token::String(key) => key,
+                _ => {
+                    return Err(self.error(
+                        self.position - 1,
+                        ParserErrorKind::ExpectedKey,
+                        "Expected key",
+                    ))
                 }
-                _ => return Err(ParserError::new("Expected key")),
-            }
+            };
+            self.expect(
+                |token| token == &Token::Colon,
+                ParserErrorKind::ExpectedColon,
+                "Expected colon",
+            )?;
+            let value = self.parse_value()?;
+            object.insert(key, value);
 
-            let next_token = self.tokens.get(self.position).unwrap().clone();
-            match next_token {
-                Token::RightBrace => {
-                    self.position += 1;
-                    return Ok(Value::Object(object));
-                }
-                Token::Comma => {
-                    self.position += 1;
-                    continue;
-                }
-                _ => return Err(ParserError::new("Expected comma or right brace")),
+            match self.expect(
+                |token| token == &Token::RightBrace || token == &Token::Comma,
+                ParserErrorKind::ExpectedCommaOrRightBrace,
+                "Expected comma or right brace",
+            )? {
+                Token::RightBrace => return Ok(Value::Object(object)),
+                _ if self.trailing_comma(&Token::RightBrace)? => return Ok(Value::Object(object)),
+                _ => continue,
             }
         }
     }
 
     fn parse_array(&mut self) -> Result<Value, ParserError> {
-        self.position += 1;
-        if self.tokens.get(self.position).unwrap() == &Token::RightBracket {
-            return Ok(Value::Array(vec![]));
-        }
         let mut array = vec![];
+        if self.peek() == Some(&Token::RightBracket) {
+            self.position += 1;
+            return Ok(Value::Array(array));
+        }
         loop {
-            array.push(self.parse()?);
-            let next_token = self.tokens.get(self.position).unwrap().clone();
-            match next_token {
-                Token::RightBracket => {
-                    self.position += 1;
-                    return Ok(Value::Array(array));
-                }
-                Token::Comma => {
-                    self.position += 1;
-                    continue;
-                }
-                _ => return Err(ParserError::new("Expected comma or right bracket")),
+            array.push(self.parse_value()?);
+            match self.expect(
+                |token| token == &Token::RightBracket || token == &Token::Comma,
+                ParserErrorKind::ExpectedCommaOrRightBracket,
+                "Expected comma or right bracket",
+            )? {
+                Token::RightBracket => return Ok(Value::Array(array)),
+                _ if self.trailing_comma(&Token::RightBracket)? => return Ok(Value::Array(array)),
+                _ => continue,
             }
         }
     }
@@ -123,11 +262,23 @@ impl Parser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lexer::Span;
+    use crate::Number;
+
+    fn spanned(tokens: Vec<Token>) -> Vec<Spanned<Token>> {
+        tokens
+            .into_iter()
+            .map(|item| Spanned {
+                item,
+                span: Span::default(),
+            })
+            .collect()
+    }
 
     #[test]
     fn string_token() {
         let tokens = vec![Token::String("abc".to_string())];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
         let expected = Value::String("abc".to_string());
         assert_eq!(ret, expected);
@@ -135,17 +286,17 @@ mod tests {
 
     #[test]
     fn number_token() {
-        let tokens = vec![Token::Number(1.0)];
-        let mut parser = Parser::new(tokens);
+        let tokens = vec![Token::Number(Number::from(1.0))];
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
-        let expected = Value::Number(1.0);
+        let expected = Value::Number(Number::from(1.0));
         assert_eq!(ret, expected);
     }
 
     #[test]
     fn true_token() {
         let tokens = vec![Token::True];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
         let expected = Value::Boolean(true);
         assert_eq!(ret, expected);
@@ -154,7 +305,7 @@ mod tests {
     #[test]
     fn false_token() {
         let tokens = vec![Token::False];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
         let expected = Value::Boolean(false);
         assert_eq!(ret, expected);
@@ -163,7 +314,7 @@ mod tests {
     #[test]
     fn null_token() {
         let tokens = vec![Token::Null];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
         let expected = Value::Null;
         assert_eq!(ret, expected);
@@ -172,9 +323,9 @@ mod tests {
     #[test]
     fn empty_object() {
         let tokens = vec![Token::LeftBrace, Token::RightBrace];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
-        let expected = Value::Object(BTreeMap::new());
+        let expected = Value::Object(Map::new());
         assert_eq!(ret, expected);
     }
 
@@ -188,9 +339,9 @@ mod tests {
             Token::String("A".to_string()),
             Token::RightBrace,
         ];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
-        let mut object = BTreeMap::new();
+        let mut object = Map::new();
         object.insert("a".to_string(), Value::String("A".to_string()));
         let expected = Value::Object(object);
         assert_eq!(ret, expected);
@@ -214,10 +365,10 @@ mod tests {
             Token::RightBrace,
             Token::RightBrace,
         ];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
-        let mut object = BTreeMap::new();
-        let mut inner_object = BTreeMap::new();
+        let mut object = Map::new();
+        let mut inner_object = Map::new();
         inner_object.insert("b".to_string(), Value::String("B".to_string()));
         inner_object.insert("c".to_string(), Value::String("C".to_string()));
         object.insert("a".to_string(), Value::Object(inner_object));
@@ -228,7 +379,7 @@ mod tests {
     #[test]
     fn empty_array() {
         let tokens = vec![Token::LeftBracket, Token::RightBracket];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
         let expected = Value::Array(vec![]);
         assert_eq!(ret, expected);
@@ -239,7 +390,7 @@ mod tests {
     fn simple_array() {
         let tokens = vec![
             Token::LeftBracket,
-            Token::Number(1.0),
+            Token::Number(Number::from(1.0)),
             Token::Comma,
             Token::String("a".to_string()),
             Token::Comma,
@@ -250,10 +401,10 @@ mod tests {
             Token::False,
             Token::RightBracket,
         ];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
         let expected = Value::Array(vec![
-            Value::Number(1.0),
+            Value::Number(Number::from(1.0)),
             Value::String("a".to_string()),
             Value::Null,
             Value::Boolean(true),
@@ -267,26 +418,141 @@ mod tests {
     fn nested_array() {
         let tokens = vec![
             Token::LeftBracket,
-            Token::Number(1.0),
+            Token::Number(Number::from(1.0)),
             Token::Comma,
             Token::LeftBracket,
-            Token::Number(2.0),
+            Token::Number(Number::from(2.0)),
             Token::Comma,
             Token::LeftBracket,
-            Token::Number(3.0),
+            Token::Number(Number::from(3.0)),
             Token::RightBracket,
             Token::RightBracket,
             Token::RightBracket,
         ];
-        let mut parser = Parser::new(tokens);
+        let mut parser = Parser::new(spanned(tokens));
         let ret = parser.parse().unwrap();
         let expected = Value::Array(vec![
-            Value::Number(1.0),
+            Value::Number(Number::from(1.0)),
             Value::Array(vec![
-                Value::Number(2.0),
-                Value::Array(vec![Value::Number(3.0)]),
+                Value::Number(Number::from(2.0)),
+                Value::Array(vec![Value::Number(Number::from(3.0))]),
             ]),
         ]);
         assert_eq!(ret, expected);
     }
+
+    #[test]
+    fn error_location() {
+        use crate::lexer::Lexer;
+
+        let json = "{\n  \"a\": 1,\n  \"b\" 2\n}";
+        let tokens = Lexer::new(json).run().unwrap();
+        let error = Parser::new(tokens.clone()).parse().unwrap_err();
+        assert_eq!(error.location, None);
+        let error = Parser::new(tokens).with_source(json).parse().unwrap_err();
+        assert_eq!(error.kind, ParserErrorKind::ExpectedColon);
+        let location = error.location.clone().unwrap();
+        assert_eq!((location.line, location.column), (3, 7));
+        assert_eq!(
+            error.to_string(),
+            "Expected colon at line 3, column 7\n  \"b\" 2\n      ^"
+        );
+
+        let error = Parser::new(spanned(vec![Token::Colon]))
+            .parse()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Unacceptable token: Colon");
+    }
+
+    fn parse_str(json: &str) -> Result<Value, ParserError> {
+        use crate::lexer::Lexer;
+
+        let tokens = Lexer::new(json).run().unwrap();
+        Parser::new(tokens).with_source(json).parse()
+    }
+
+    #[test]
+    fn empty_containers_in_containers() {
+        let ret = parse_str(r#"{"a": [], "b": {}, "c": [[], {}]}"#).unwrap();
+        let mut object = Map::new();
+        object.insert("a".to_string(), Value::Array(vec![]));
+        object.insert("b".to_string(), Value::Object(Map::new()));
+        object.insert(
+            "c".to_string(),
+            Value::Array(vec![Value::Array(vec![]), Value::Object(Map::new())]),
+        );
+        assert_eq!(ret, Value::Object(object));
+    }
+
+    #[test]
+    fn malformed_input() {
+        let cases = [
+            ("", ParserErrorKind::UnexpectedEof),
+            ("[", ParserErrorKind::UnexpectedEof),
+            ("[1", ParserErrorKind::UnexpectedEof),
+            ("[1,", ParserErrorKind::UnexpectedEof),
+            ("{", ParserErrorKind::UnexpectedEof),
+            (r#"{"a":"#, ParserErrorKind::UnexpectedEof),
+            (r#"{"a" 1}"#, ParserErrorKind::ExpectedColon),
+            (r#"{1: 1}"#, ParserErrorKind::ExpectedKey),
+            (
+                r#"{"a": 1 "b": 2}"#,
+                ParserErrorKind::ExpectedCommaOrRightBrace,
+            ),
+            ("[1 2]", ParserErrorKind::ExpectedCommaOrRightBracket),
+            ("]", ParserErrorKind::UnexpectedToken),
+            ("[,]", ParserErrorKind::UnexpectedToken),
+        ];
+        for (json, kind) in cases {
+            assert_eq!(parse_str(json).map_err(|e| e.kind), Err(kind), "{}", json);
+        }
+        let deep = "[".repeat(MAX_DEPTH + 1);
+        assert_eq!(
+            parse_str(&deep).map_err(|e| e.kind),
+            Err(ParserErrorKind::TooDeep)
+        );
+        let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert!(parse_str(&nested).is_ok());
+    }
+
+    /// Feed truncated documents and random token soups to the lexer and the
+    /// parser. Any panic fails the test.
+    #[test]
+    fn fuzz_corpus_never_panics() {
+        use crate::lexer::Lexer;
+
+        let document = r#"{"name": "中坂\n\u00e9", "n": [-1.5e3, 0, true, false, null],
+            "o": {"a": {}, "b": [[]]}, "s": "\ud83e\udd80"}"#;
+        let mut corpus: Vec<String> = (0..=document.len())
+            .filter(|&i| document.is_char_boundary(i))
+            .map(|i| document[..i].to_string())
+            .collect();
+
+        let pieces = [
+            "{", "}", "[", "]", ":", ",", "\"", "\\", "\"a\"", "1", "-", "0", ".", "e", "+",
+            "true", "tru", "null", "false", " ", "\n", "\\u", "d83e", "é", "\u{1}",
+        ];
+        // xorshift, so that the corpus is the same on every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..5000 {
+            let mut input = String::new();
+            for _ in 0..(state % 24) {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                input.push_str(pieces[(state % pieces.len() as u64) as usize]);
+            }
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            corpus.push(input);
+        }
+
+        for input in &corpus {
+            if let Ok(tokens) = Lexer::new(input).run() {
+                let _ = Parser::new(tokens.clone()).with_source(input).parse();
+                let _ = Parser::new(tokens).lenient().parse();
+            }
+        }
+    }
 }
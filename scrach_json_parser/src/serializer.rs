@@ -0,0 +1,330 @@
+use crate::{Number, Value};
+use std::fmt;
+use std::io;
+
+/// What to write for NaN and infinite numbers, which JSON cannot represent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Fail with `SerializeError::NonFiniteNumber`.
+    Error,
+    /// Write `null`.
+    Null,
+    /// Write the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    String,
+}
+
+#[derive(Debug)]
+pub enum SerializeError {
+    Io(io::Error),
+    NonFiniteNumber(f64),
+    /// A number an IEEE 754 double does not hold exactly, in canonical JSON.
+    InexactNumber(Number),
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerializeError::Io(e) => write!(f, "{}", e),
+            SerializeError::NonFiniteNumber(n) => {
+                write!(f, "{} cannot be represented in JSON", n)
+            }
+            SerializeError::InexactNumber(n) => {
+                write!(f, "{} cannot be represented exactly as a double", n)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SerializeError::Io(e) => Some(e),
+            SerializeError::NonFiniteNumber(_) | SerializeError::InexactNumber(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for SerializeError {
+    fn from(e: io::Error) -> SerializeError {
+        SerializeError::Io(e)
+    }
+}
+
+/// Writer of values as JSON text. Compact by default, or pretty printed with
+/// one member per line when an indentation is set. Object members are
+/// written in insertion order, or sorted by key when asked to.
+#[derive(Debug, Clone)]
+pub struct Serializer {
+    indent: Option<String>,
+    non_finite: NonFinite,
+    sort_keys: bool,
+}
+
+impl Default for Serializer {
+    fn default() -> Serializer {
+        Serializer::new()
+    }
+}
+
+impl Serializer {
+    pub fn new() -> Serializer {
+        Serializer {
+            indent: None,
+            non_finite: NonFinite::Error,
+            sort_keys: false,
+        }
+    }
+
+    /// Pretty print with two spaces per level.
+    pub fn pretty() -> Serializer {
+        Serializer::new().with_indent("  ")
+    }
+
+    /// Pretty print with `indent` per level.
+    pub fn with_indent(mut self, indent: &str) -> Serializer {
+        self.indent = Some(indent.to_string());
+        self
+    }
+
+    pub fn with_non_finite(mut self, non_finite: NonFinite) -> Serializer {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// Write object members sorted by the bytes of their keys. Use
+    /// `canonical` for the UTF-16 order of RFC 8785.
+    pub fn with_sorted_keys(mut self) -> Serializer {
+        self.sort_keys = true;
+        self
+    }
+
+    pub fn to_string(&self, value: &Value) -> Result<String, SerializeError> {
+        let mut bytes = vec![];
+        self.write(&mut bytes, value)?;
+        Ok(String::from_utf8(bytes).expect("serializer writes UTF-8"))
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W, value: &Value) -> Result<(), SerializeError> {
+        self.write_value(writer, value, 0)
+    }
+
+    fn write_value<W: io::Write>(
+        &self,
+        w: &mut W,
+        value: &Value,
+        depth: usize,
+    ) -> Result<(), SerializeError> {
+        match value {
+            Value::Null => w.write_all(b"null")?,
+            Value::Boolean(b) => write!(w, "{}", b)?,
+            Value::Number(n) => self.write_number(w, n)?,
+            Value::String(s) => write_string(w, s)?,
+            Value::Array(array) => {
+                if array.is_empty() {
+                    w.write_all(b"[]")?;
+                    return Ok(());
+                }
+                w.write_all(b"[")?;
+                for (i, element) in array.iter().enumerate() {
+                    if i > 0 {
+                        w.write_all(b",")?;
+                    }
+                    self.write_newline(w, depth + 1)?;
+                    self.write_value(w, element, depth + 1)?;
+                }
+                self.write_newline(w, depth)?;
+                w.write_all(b"]")?;
+            }
+            Value::Object(object) => {
+                if object.is_empty() {
+                    w.write_all(b"{}")?;
+                    return Ok(());
+                }
+                w.write_all(b"{")?;
+                let members: Vec<(&String, &Value)> = if self.sort_keys {
+                    object.sorted().collect()
+                } else {
+                    object.iter().collect()
+                };
+                for (i, (key, element)) in members.into_iter().enumerate() {
+                    if i > 0 {
+                        w.write_all(b",")?;
+                    }
+                    self.write_newline(w, depth + 1)?;
+                    write_string(w, key)?;
+                    w.write_all(if self.indent.is_some() { b": " } else { b":" })?;
+                    self.write_value(w, element, depth + 1)?;
+                }
+                self.write_newline(w, depth)?;
+                w.write_all(b"}")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Start a new line indented `depth` times when pretty printing.
+    fn write_newline<W: io::Write>(&self, w: &mut W, depth: usize) -> io::Result<()> {
+        if let Some(indent) = &self.indent {
+            w.write_all(b"\n")?;
+            for _ in 0..depth {
+                w.write_all(indent.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_number<W: io::Write>(&self, w: &mut W, n: &Number) -> Result<(), SerializeError> {
+        let f = n.to_f64();
+        if n.as_str().is_none() && n.is_f64() && !f.is_finite() {
+            match self.non_finite {
+                NonFinite::Error => return Err(SerializeError::NonFiniteNumber(f)),
+                NonFinite::Null => w.write_all(b"null")?,
+                NonFinite::String => {
+                    let name = if f.is_nan() {
+                        "NaN"
+                    } else if f > 0.0 {
+                        "Infinity"
+                    } else {
+                        "-Infinity"
+                    };
+                    write_string(w, name)?;
+                }
+            }
+            return Ok(());
+        }
+        // Floats are written in the shortest form that reads back the same.
+        write!(w, "{}", n)?;
+        Ok(())
+    }
+}
+
+/// Write `s` quoted, escaping quotes, backslashes and control characters.
+pub(crate) fn write_string<W: io::Write>(w: &mut W, s: &str) -> io::Result<()> {
+    w.write_all(b"\"")?;
+    let bytes = s.as_bytes();
+    let mut start = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let escape: &[u8] = match byte {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x08 => b"\\b",
+            0x0c => b"\\f",
+            0x00..=0x1f => b"",
+            _ => continue,
+        };
+        w.write_all(&bytes[start..i])?;
+        if escape.is_empty() {
+            write!(w, "\\u{:04x}", byte)?;
+        } else {
+            w.write_all(escape)?;
+        }
+        start = i + 1;
+    }
+    w.write_all(&bytes[start..])?;
+    w.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    #[test]
+    fn compact() {
+        let value =
+            from_str(r#"{"b": [1, 2.5, -3, true, null], "a": {"x": "y"}, "e": [], "o": {}}"#)
+                .unwrap();
+        assert_eq!(
+            Serializer::new().to_string(&value).unwrap(),
+            r#"{"b":[1,2.5,-3,true,null],"a":{"x":"y"},"e":[],"o":{}}"#
+        );
+        assert_eq!(
+            Serializer::new()
+                .with_sorted_keys()
+                .to_string(&value)
+                .unwrap(),
+            r#"{"a":{"x":"y"},"b":[1,2.5,-3,true,null],"e":[],"o":{}}"#
+        );
+    }
+
+    #[test]
+    fn pretty() {
+        let value = from_str(r#"{"b": [1, {"c": null}], "a": {}}"#).unwrap();
+        assert_eq!(
+            Serializer::pretty()
+                .with_sorted_keys()
+                .to_string(&value)
+                .unwrap(),
+            "{\n  \"a\": {},\n  \"b\": [\n    1,\n    {\n      \"c\": null\n    }\n  ]\n}"
+        );
+        assert_eq!(
+            Serializer::new()
+                .with_indent("\t")
+                .to_string(&from_str("[1]").unwrap())
+                .unwrap(),
+            "[\n\t1\n]"
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let value = Value::String("\"\\/\u{8}\u{c}\n\r\t\u{1}\u{1f}é🦀".to_string());
+        let json = Serializer::new().to_string(&value).unwrap();
+        assert_eq!(json, r#""\"\\/\b\f\n\r\t\u0001\u001fé🦀""#);
+        assert_eq!(from_str(&json).unwrap(), value);
+    }
+
+    #[test]
+    fn floats_round_trip() {
+        for f in [
+            0.1,
+            -0.0,
+            1.0,
+            1e-7,
+            123456.789,
+            1.7976931348623157e308,
+            5e-324,
+            std::f64::consts::PI,
+            2.0_f64.powi(60),
+        ] {
+            let value = Value::Number(Number::from(f));
+            let json = Serializer::new().to_string(&value).unwrap();
+            assert_eq!(from_str(&json).unwrap(), value, "{}", json);
+        }
+        let big = from_str("18446744073709551615").unwrap();
+        assert_eq!(
+            Serializer::new().to_string(&big).unwrap(),
+            "18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn non_finite() {
+        let value = Value::Array(vec![
+            Value::Number(Number::from(f64::NAN)),
+            Value::Number(Number::from(f64::INFINITY)),
+            Value::Number(Number::from(f64::NEG_INFINITY)),
+        ]);
+        assert!(matches!(
+            Serializer::new().to_string(&value),
+            Err(SerializeError::NonFiniteNumber(_))
+        ));
+        assert_eq!(
+            Serializer::new()
+                .with_non_finite(NonFinite::Null)
+                .to_string(&value)
+                .unwrap(),
+            "[null,null,null]"
+        );
+        assert_eq!(
+            Serializer::new()
+                .with_non_finite(NonFinite::String)
+                .to_string(&value)
+                .unwrap(),
+            r#"["NaN","Infinity","-Infinity"]"#
+        );
+    }
+}